@@ -0,0 +1,164 @@
+//! Benchmarks of world generation and simulation that run without a window.
+//!
+//! `cargo bench` measures every benchmark with criterion, `cargo bench -- NAME` only those
+//! whose name matches NAME. `cargo test --benches` runs each routine once to check they
+//! still work.
+//! Chunk generation logs to stderr, `cargo bench 2>/dev/null` keeps the report readable.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use macroquad::math::{vec2, Vec2};
+use macroquad::rand;
+
+use venturemmo::special::chunk::Chunk;
+use venturemmo::special::config::WorldConfig;
+use venturemmo::special::heightfield::Heightfield;
+use venturemmo::special::noise::Noise;
+use venturemmo::special::noise_graph::NoiseNode;
+use venturemmo::world::{ChunkPosition, World, WorldSettings};
+
+const SEED: u64 = 0;
+
+fn chunk_generation(c: &mut Criterion) {
+    let config = WorldConfig::default();
+    let noise = noise();
+    let mut group = c.benchmark_group("chunk generation");
+    for position in [
+        ChunkPosition::new(0, 0),
+        ChunkPosition::new(-3, 7),
+        ChunkPosition::new(120, -45),
+    ] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(position),
+            &position,
+            |b, &position| {
+                b.iter(|| {
+                    let mut chunk = Chunk::new(position, config);
+                    chunk.populate(&noise);
+                    chunk
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn heightfield_sampling(c: &mut Criterion) {
+    let config = WorldConfig::default();
+    let noise = noise();
+    let mut group = c.benchmark_group("heightfield sampling");
+    for size in [config.chunk_size + 1, 64, config.noise_image_size] {
+        let spacing = config.chunk_world_size() / f32::from(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| Heightfield::sample(&noise, &config, size, Vec2::ZERO, spacing));
+        });
+    }
+    group.finish();
+}
+
+fn chunk_update(c: &mut Criterion) {
+    let config = WorldConfig::default();
+    let mut group = c.benchmark_group("chunk update");
+    for count in [100, 1000] {
+        group.bench_function(format!("{count} movers"), |b| {
+            b.iter_batched(
+                || {
+                    crowded_chunk(&config, count, |chunk, position| {
+                        chunk.add_random_mover(position, 0.0, 10.0, 2.0);
+                    })
+                },
+                |mut chunk| {
+                    chunk.update(&[]);
+                    chunk
+                },
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_function(format!("{count} followers"), |b| {
+            b.iter_batched(
+                || crowded_chunk(&config, count, Chunk::add_follower),
+                |mut chunk| {
+                    chunk.update(&[]);
+                    chunk
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// Half of the movers are outside of the chunk and get extracted.
+fn entity_extraction(c: &mut Criterion) {
+    let config = WorldConfig::default();
+    let chunk_size = config.chunk_world_size();
+    let mut group = c.benchmark_group("extract outside entities");
+    for count in [100, 1000, 10_000] {
+        group.bench_function(format!("{count} crossers"), |b| {
+            b.iter_batched(
+                || {
+                    let mut index = 0;
+                    crowded_chunk(&config, count * 2, |chunk, position| {
+                        let offset = if index % 2 == 0 { 0.0 } else { chunk_size };
+                        index += 1;
+                        chunk.add_random_mover(position + vec2(offset, 0.0), 0.0, 10.0, 2.0);
+                    })
+                },
+                |mut chunk| chunk.extract_outside_entities(),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn world_tick(c: &mut Criterion) {
+    let config = WorldConfig::default();
+    let mut world = World::headless(WorldSettings {
+        seed: SEED,
+        noise_frequency: config.noise_frequency,
+    });
+    world.set_config(config);
+    world.setup();
+    // A chunk every 60 ticks, so generation is part of the measurement.
+    let step = vec2(config.chunk_world_size() / 60.0, 0.0);
+    c.bench_function("world tick/moving player", |b| {
+        b.iter(|| {
+            world.move_player(step);
+            world.simulate();
+        });
+    });
+}
+
+fn noise() -> Noise {
+    let mut noise = Noise::new();
+    noise.set_graph(
+        &NoiseNode::default(),
+        SEED,
+        WorldConfig::default().noise_frequency,
+    );
+    noise
+}
+
+/// A chunk at the origin with `count` entities spread over it by `add`.
+fn crowded_chunk(config: &WorldConfig, count: u32, mut add: impl FnMut(&mut Chunk, Vec2)) -> Chunk {
+    rand::srand(SEED);
+    let chunk_size = config.chunk_world_size();
+    let mut chunk = Chunk::new(ChunkPosition::new(0, 0), *config);
+    for _ in 0..count {
+        let position = vec2(
+            rand::gen_range(0.0, chunk_size),
+            rand::gen_range(0.0, chunk_size),
+        );
+        add(&mut chunk, position);
+    }
+    chunk
+}
+
+criterion_group!(
+    benches,
+    chunk_generation,
+    heightfield_sampling,
+    chunk_update,
+    entity_extraction,
+    world_tick
+);
+criterion_main!(benches);
@@ -0,0 +1,303 @@
+use std::fmt::Display;
+
+use crate::common::parse_seed;
+use crate::special::camera::ResizePolicy;
+use crate::world::ChunkPosition;
+
+pub const USAGE: &str = "usage:
+    venturemmo [--seed SEED] [--frequency F] [--noise FILE] [--resize scale|fit]
+               [--config FILE] [--set NAME=VALUE]...
+        start the game, skipping the world creation screen if a seed is given.
+        seeds that aren't numbers are hashed, FILE is a noise graph in ron.
+        on resize the view keeps its scale (default) or fits the same world area
+    venturemmo export --from X,Y --to X,Y [--seed N[,N...]] [--cell-pixels N] [--output FILE]
+                      [--noise FILE] [--config FILE] [--set NAME=VALUE]...
+        render the chunks between --from and --to (inclusive) to a png without opening a window,
+        one panel per seed, side by side
+    venturemmo bench [--frames N] [--seed SEED] [--output FILE] [--config FILE] [--set NAME=VALUE]...
+        simulate N frames (default 600) without a window while walking across chunks, then print
+        the time spent per profiler zone and write every frame's zones to FILE (default
+        profile.csv), as json if FILE ends in .json
+    all take the world config from a ron file, --set overrides single values: chunk_size,
+    chunk_tile_size, noise_image_size, noise_frequency, render_distance, update_distance,
+    update_threads, full_rate_distance, far_update_interval";
+
+pub enum Command {
+    Play(PlayOptions),
+    Export(ExportOptions),
+    Bench(BenchOptions),
+}
+
+#[derive(Default)]
+pub struct PlayOptions {
+    pub seed: Option<u64>,
+    pub noise_frequency: Option<f32>,
+    pub noise_graph: Option<String>,
+    pub resize_policy: ResizePolicy,
+    pub config: ConfigOptions,
+}
+
+/// Where the `WorldConfig` comes from.
+#[derive(Default)]
+pub struct ConfigOptions {
+    pub path: Option<String>,
+    /// `--set` values by name, in the order given.
+    pub overrides: Vec<(String, String)>,
+}
+
+pub struct ExportOptions {
+    pub from: ChunkPosition,
+    pub to: ChunkPosition,
+    pub seeds: Vec<u64>,
+    /// Size of one chunk cell in the exported image.
+    pub cell_pixels: u16,
+    pub output: String,
+    pub noise_graph: Option<String>,
+    pub config: ConfigOptions,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            from: ChunkPosition::new(-2, -2),
+            to: ChunkPosition::new(2, 2),
+            seeds: vec![0],
+            cell_pixels: 4,
+            output: "world.png".to_owned(),
+            noise_graph: None,
+            config: ConfigOptions::default(),
+        }
+    }
+}
+
+pub struct BenchOptions {
+    pub frames: u32,
+    pub seed: u64,
+    pub output: String,
+    pub config: ConfigOptions,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            frames: 600,
+            seed: 0,
+            output: "profile.csv".to_owned(),
+            config: ConfigOptions::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parses the command line arguments, without the program name.
+///
+/// # Errors
+///
+/// Fails on unknown subcommands, unknown options and malformed values.
+pub fn parse(args: &[String]) -> Result<Command, CliError> {
+    match args.first().map(String::as_str) {
+        Some("export") => parse_export(args.iter().skip(1)).map(Command::Export),
+        Some("bench") => parse_bench(args.iter().skip(1)).map(Command::Bench),
+        None => Ok(Command::Play(PlayOptions::default())),
+        Some(first) if first.starts_with("--") => parse_play(args.iter()).map(Command::Play),
+        Some(other) => Err(CliError(format!("unknown command `{other}`"))),
+    }
+}
+
+fn parse_play<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<PlayOptions, CliError> {
+    let mut options = PlayOptions::default();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| CliError(format!("`{flag}` needs a value")))?;
+        match flag.as_str() {
+            "--seed" => options.seed = Some(parse_seed(value)),
+            "--frequency" => options.noise_frequency = Some(parse_number(value)?),
+            "--noise" => options.noise_graph = Some(value.clone()),
+            "--resize" => options.resize_policy = parse_resize_policy(value)?,
+            "--config" => options.config.path = Some(value.clone()),
+            "--set" => options.config.overrides.push(parse_override(value)?),
+            _ => return Err(CliError(format!("unknown option `{flag}`"))),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_resize_policy(value: &str) -> Result<ResizePolicy, CliError> {
+    match value {
+        "scale" => Ok(ResizePolicy::ConstantScale),
+        "fit" => Ok(ResizePolicy::Fit),
+        _ => Err(CliError(format!(
+            "expected `scale` or `fit` for `--resize`, got `{value}`"
+        ))),
+    }
+}
+
+fn parse_export<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<ExportOptions, CliError> {
+    let mut options = ExportOptions::default();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| CliError(format!("`{flag}` needs a value")))?;
+        match flag.as_str() {
+            "--from" => options.from = parse_chunk_position(value)?,
+            "--to" => options.to = parse_chunk_position(value)?,
+            "--seed" => options.seeds = value.split(',').map(parse_seed).collect(),
+            "--cell-pixels" => options.cell_pixels = parse_number(value)?,
+            "--output" => options.output.clone_from(value),
+            "--noise" => options.noise_graph = Some(value.clone()),
+            "--config" => options.config.path = Some(value.clone()),
+            "--set" => options.config.overrides.push(parse_override(value)?),
+            _ => return Err(CliError(format!("unknown option `{flag}`"))),
+        }
+    }
+    if options.cell_pixels == 0 {
+        return Err(CliError("`--cell-pixels` must be at least 1".to_owned()));
+    }
+    Ok(options)
+}
+
+fn parse_bench<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<BenchOptions, CliError> {
+    let mut options = BenchOptions::default();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| CliError(format!("`{flag}` needs a value")))?;
+        match flag.as_str() {
+            "--frames" => options.frames = parse_number(value)?,
+            "--seed" => options.seed = parse_seed(value),
+            "--output" => options.output.clone_from(value),
+            "--config" => options.config.path = Some(value.clone()),
+            "--set" => options.config.overrides.push(parse_override(value)?),
+            _ => return Err(CliError(format!("unknown option `{flag}`"))),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_override(value: &str) -> Result<(String, String), CliError> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or_else(|| CliError(format!("expected NAME=VALUE for `--set`, got `{value}`")))?;
+    Ok((name.trim().to_owned(), value.trim().to_owned()))
+}
+
+fn parse_chunk_position(value: &str) -> Result<ChunkPosition, CliError> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| CliError(format!("expected a chunk position as X,Y, got `{value}`")))?;
+    Ok(ChunkPosition::new(
+        parse_number(x.trim())?,
+        parse_number(y.trim())?,
+    ))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("`{value}` is not a valid number")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Command, CliError> {
+        let args: Vec<String> = line.split_whitespace().map(str::to_owned).collect();
+        parse(&args)
+    }
+
+    fn error(line: &str) -> String {
+        match parse_line(line) {
+            Ok(_) => panic!("`{line}` should not parse"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn play_options_are_parsed() {
+        let Ok(Command::Play(options)) = parse_line("") else {
+            panic!("no arguments should start the game");
+        };
+        assert_eq!(options.seed, None);
+
+        let line = "--seed 5 --frequency 0.01 --noise graph.ron --resize fit --set chunk_size=8";
+        let Ok(Command::Play(options)) = parse_line(line) else {
+            panic!("`{line}` should parse");
+        };
+        assert_eq!(options.seed, Some(5));
+        assert_eq!(options.noise_frequency, Some(0.01));
+        assert_eq!(options.noise_graph.as_deref(), Some("graph.ron"));
+        assert_eq!(options.resize_policy, ResizePolicy::Fit);
+        assert_eq!(
+            options.config.overrides,
+            [("chunk_size".to_owned(), "8".to_owned())]
+        );
+    }
+
+    #[test]
+    fn invalid_play_options_are_rejected() {
+        assert!(error("--resize stretch").contains("`stretch`"));
+        assert!(error("--frequency fast").contains("`fast`"));
+        assert!(error("--zoom 2").contains("unknown option `--zoom`"));
+        assert!(error("--seed").contains("`--seed` needs a value"));
+        assert!(error("--set chunk_size").contains("NAME=VALUE"));
+        assert!(error("explore").contains("unknown command `explore`"));
+    }
+
+    #[test]
+    fn export_options_are_parsed() {
+        let line = "export --from -1,2 --to 3,-4 --seed 1,two --cell-pixels 8 --output out.png";
+        let Ok(Command::Export(options)) = parse_line(line) else {
+            panic!("`{line}` should parse");
+        };
+        assert_eq!(options.from, ChunkPosition::new(-1, 2));
+        assert_eq!(options.to, ChunkPosition::new(3, -4));
+        assert_eq!(options.seeds, [1, parse_seed("two")]);
+        assert_eq!(options.cell_pixels, 8);
+        assert_eq!(options.output, "out.png");
+    }
+
+    #[test]
+    fn invalid_export_options_are_rejected() {
+        assert!(error("export --cell-pixels 0").contains("at least 1"));
+        assert!(error("export --cell-pixels -3").contains("`-3`"));
+        for position in ["1", "1;2", "a,b", "1,2,3", ","] {
+            assert!(
+                parse_line(&format!("export --from {position}")).is_err(),
+                "`{position}` should not be a chunk position"
+            );
+        }
+        assert!(error("export --from 1,2 --to").contains("`--to` needs a value"));
+        assert!(error("export --scale 2").contains("unknown option `--scale`"));
+    }
+
+    #[test]
+    fn bench_options_are_parsed() {
+        let line = "bench --frames 10 --seed 3 --output profile.json --config world.ron";
+        let Ok(Command::Bench(options)) = parse_line(line) else {
+            panic!("`{line}` should parse");
+        };
+        assert_eq!(options.frames, 10);
+        assert_eq!(options.seed, 3);
+        assert_eq!(options.output, "profile.json");
+        assert_eq!(options.config.path.as_deref(), Some("world.ron"));
+    }
+
+    #[test]
+    fn invalid_bench_options_are_rejected() {
+        assert!(error("bench --frames -1").contains("`-1`"));
+        assert!(error("bench --frames").contains("`--frames` needs a value"));
+        assert!(error("bench --threads 4").contains("unknown option `--threads`"));
+    }
+}
@@ -1,5 +1,3 @@
-
-
 #[must_use]
 pub fn lerp(from: f32, to: f32, p: f32) -> f32 {
     from.mul_add(1.0 - p, to * p)
@@ -10,7 +8,171 @@ pub fn map(value: f32, start1: f32, stop1: f32, start2: f32, stop2: f32) -> f32
     ((value - start1) / (stop1 - start1)).mul_add(stop2 - start2, start2)
 }
 
+/// Seeds typed as numbers are used as they are, anything else is hashed. The hash is
+/// FNV-1a, so a named seed gives the same world on every platform and build.
+#[must_use]
+pub fn parse_seed(text: &str) -> u64 {
+    let text = text.trim();
+    text.parse().unwrap_or_else(|_| {
+        text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    })
+}
+
+/// The closest `f32` to `value`, for times and other values that are kept in `f64`.
+#[must_use]
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+pub const fn narrow(value: f64) -> f32 {
+    value as f32
+}
+
+/// `value` rounded down, `None` when it isn't a number or doesn't fit an `i32`.
+#[must_use]
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+pub fn floor_i32(value: f32) -> Option<i32> {
+    let value = value.floor();
+    (f64::from(i32::MIN)..=f64::from(i32::MAX))
+        .contains(&f64::from(value))
+        .then_some(value as i32)
+}
+
 #[must_use]
 pub fn norm(value: f32, start: f32, stop: f32) -> f32 {
     map(value, start, stop, 0.0, 1.0)
 }
+
+/// The PCG generator behind `macroquad::rand`, but owned instead of global, so whatever
+/// it generates doesn't depend on what else drew numbers before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+    const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.rand();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.rand();
+        rng
+    }
+
+    /// Any `u32`.
+    pub fn rand(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) & u64::from(u32::MAX);
+        let xorshifted = u32::try_from(xorshifted).unwrap_or_default();
+        xorshifted.rotate_right(u32::try_from(old >> 59).unwrap_or_default())
+    }
+
+    /// In `low..high`.
+    pub fn range_u32(&mut self, low: u32, high: u32) -> u32 {
+        if high <= low {
+            return low;
+        }
+        low + self.rand() % (high - low)
+    }
+
+    /// In `low..high`.
+    pub fn range_f32(&mut self, low: f32, high: f32) -> f32 {
+        // The top 23 bits as the mantissa of a float in 1..2.
+        let unit = f32::from_bits(0x3f80_0000 | (self.rand() >> 9)) - 1.0;
+        lerp(low, high, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASES: usize = 1000;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-3 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    /// Random values between -1000 and 1000.
+    fn values(seed: u64) -> impl FnMut() -> f32 {
+        let mut rng = Rng::new(seed);
+        move || rng.range_f32(-1000.0, 1000.0)
+    }
+
+    #[test]
+    fn floor_i32_checks_the_range() {
+        assert_eq!(floor_i32(-0.5), Some(-1));
+        assert_eq!(floor_i32(7.99), Some(7));
+        assert_eq!(floor_i32(-1e9), Some(-1_000_000_000));
+        assert_eq!(floor_i32(3e9), None);
+        assert_eq!(floor_i32(-3e9), None);
+        assert_eq!(floor_i32(f32::NAN), None);
+        assert_eq!(floor_i32(f32::INFINITY), None);
+    }
+
+    #[test]
+    fn lerp_goes_from_one_end_to_the_other() {
+        let mut next = values(1);
+        for _ in 0..CASES {
+            let (from, to, p) = (next(), next(), next().abs() / 1000.0);
+            assert!(close(lerp(from, to, 0.0), from));
+            assert!(close(lerp(from, to, 1.0), to));
+            let between = lerp(from, to, p);
+            assert!(between >= from.min(to) - 1e-3 && between <= from.max(to) + 1e-3);
+            assert!(close(lerp(from, to, p), lerp(to, from, 1.0 - p)));
+        }
+    }
+
+    #[test]
+    fn map_is_linear_and_invertible() {
+        let mut next = values(2);
+        for _ in 0..CASES {
+            let (start1, stop1, start2, stop2, value) = (next(), next(), next(), next(), next());
+            if (stop1 - start1).abs() < 1.0 || (stop2 - start2).abs() < 1.0 {
+                continue;
+            }
+            assert!(close(map(start1, start1, stop1, start2, stop2), start2));
+            assert!(close(map(stop1, start1, stop1, start2, stop2), stop2));
+            let mapped = map(value, start1, stop1, start2, stop2);
+            assert!(close(map(mapped, start2, stop2, start1, stop1), value));
+            let middle = map(f32::midpoint(start1, stop1), start1, stop1, start2, stop2);
+            assert!(close(middle, f32::midpoint(start2, stop2)));
+        }
+    }
+
+    #[test]
+    fn norm_maps_onto_the_unit_range() {
+        let mut next = values(3);
+        for _ in 0..CASES {
+            let (start, stop, p) = (next(), next(), next().abs() / 1000.0);
+            if (stop - start).abs() < 1.0 {
+                continue;
+            }
+            assert!(close(norm(start, start, stop), 0.0));
+            assert!(close(norm(stop, start, stop), 1.0));
+            assert!(close(norm(lerp(start, stop, p), start, stop), p));
+        }
+    }
+
+    #[test]
+    fn rng_is_repeatable_and_stays_in_range() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut other = Rng::new(43);
+        let first: Vec<u32> = (0..8).map(|_| a.rand()).collect();
+        assert_eq!(first, (0..8).map(|_| b.rand()).collect::<Vec<_>>());
+        assert_ne!(first, (0..8).map(|_| other.rand()).collect::<Vec<_>>());
+        for _ in 0..CASES {
+            assert!((3..7).contains(&a.range_u32(3, 7)));
+            let value = a.range_f32(-2.0, 5.0);
+            assert!((-2.0..=5.0).contains(&value));
+        }
+        assert_eq!(a.range_u32(4, 4), 4);
+    }
+}
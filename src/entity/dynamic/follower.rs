@@ -1,55 +1,190 @@
-use macroquad::color::DARKGRAY;
-use macroquad::math::{Rect, Vec2};
+use std::collections::HashMap;
 
-use macroquad::shapes::draw_poly;
+use macroquad::math::Vec2;
 
-use crate::entity::dynamic::updatable::Update;
-use crate::special::chunk::Chunk;
+use crate::common::floor_i32;
+use crate::entity::dynamic::{Behaviour, Dynamic, Shape};
 
-pub struct Follower {
-    position: Vec2,
-    target: Option<Vec2>,
+const FOLLOWER_SIZE: f32 = 20.0;
+/// Entities closer than this are already caught up with.
+const MIN_DISTANCE: f32 = 50.0;
+/// Entities further away than this are out of sight.
+const MAX_DISTANCE: f32 = 400.0;
+const SPEED: f32 = 0.3;
+
+#[must_use]
+pub const fn follower(position: Vec2) -> Dynamic {
+    Dynamic {
+        position,
+        velocity: Vec2::ZERO,
+        shape: Shape {
+            sides: 10,
+            size: FOLLOWER_SIZE,
+            rotation: 0.0,
+        },
+        behaviour: Behaviour::Follower { target: None },
+    }
 }
 
-impl Follower {
-    #[must_use]
-    pub const fn new(position: Vec2) -> Self {
-        Self {
-            position,
-            target: None,
-        }
+/// Points every follower at the closest entity in sight.
+///
+/// Reads the positions from before anything moved this update. `neighbours` are the
+/// positions of entities in other chunks that can be seen too. Over a step of several
+/// `ticks` followers slow down so they don't overshoot their target.
+pub fn follow(
+    positions: &[Vec2],
+    neighbours: &[&[Vec2]],
+    behaviours: &mut [Behaviour],
+    velocities: &mut [Vec2],
+    ticks: u16,
+) {
+    if !behaviours
+        .iter()
+        .any(|behaviour| matches!(*behaviour, Behaviour::Follower { .. }))
+    {
+        return;
     }
+    let ticks = f32::from(ticks);
+    let grid = SightGrid::new(positions.iter().chain(neighbours.iter().copied().flatten()));
+    for ((&position, behaviour), velocity) in positions
+        .iter()
+        .zip(behaviours.iter_mut())
+        .zip(velocities.iter_mut())
+    {
+        let Behaviour::Follower { ref mut target } = *behaviour else {
+            continue;
+        };
+        *target = grid.closest_in_sight(position);
+        *velocity = target.map_or(Vec2::ZERO, |target| {
+            let speed = SPEED.min((position.distance(target) - MIN_DISTANCE) / ticks);
+            (target - position).normalize() * speed
+        });
+    }
+}
+
+/// Positions bucketed into cells as wide as followers can see, so only the cells around a
+/// follower have to be searched. Positions too far out for a cell are never seen.
+struct SightGrid {
+    /// Every position with the order it was added in, which breaks ties between equally
+    /// close ones.
+    cells: HashMap<(i32, i32), Vec<(usize, Vec2)>>,
 }
 
-impl Update for Follower {
-    fn get_pos(&self) -> Vec2 {
-        self.position
+impl SightGrid {
+    fn new<'a>(positions: impl Iterator<Item = &'a Vec2>) -> Self {
+        let mut cells: HashMap<_, Vec<_>> = HashMap::new();
+        for (order, &position) in positions.enumerate() {
+            if let Some(cell) = Self::cell(position) {
+                cells.entry(cell).or_default().push((order, position));
+            }
+        }
+        Self { cells }
+    }
+
+    fn cell(position: Vec2) -> Option<(i32, i32)> {
+        Some((
+            floor_i32(position.x / MAX_DISTANCE)?,
+            floor_i32(position.y / MAX_DISTANCE)?,
+        ))
     }
 
-    fn update(&mut self, chunk: &mut Chunk) {
-        let mut min_distance = f32::INFINITY;
-        for entity in &chunk.dynamics {
-            if let Some(entity) = entity.as_ref() {
-                let pos = entity.get_pos();
-                let distance = self.position.distance(pos);
-                if distance < 400.0 && distance < min_distance && distance > 50.0 {
-                    min_distance = distance;
-                    self.target = Some(pos);
+    /// The closest position further than `MIN_DISTANCE` and nearer than `MAX_DISTANCE`.
+    fn closest_in_sight(&self, position: Vec2) -> Option<Vec2> {
+        let (x, y) = Self::cell(position)?;
+        let mut closest: Option<(f32, usize, Vec2)> = None;
+        for cell_y in y.saturating_sub(1)..=y.saturating_add(1) {
+            for cell_x in x.saturating_sub(1)..=x.saturating_add(1) {
+                let Some(cell) = self.cells.get(&(cell_x, cell_y)) else {
+                    continue;
+                };
+                for &(order, other) in cell {
+                    let distance = position.distance(other);
+                    if distance <= MIN_DISTANCE || distance >= MAX_DISTANCE {
+                        continue;
+                    }
+                    let closer = closest.is_none_or(|(best, best_order, _)| {
+                        distance
+                            .total_cmp(&best)
+                            .then(order.cmp(&best_order))
+                            .is_lt()
+                    });
+                    if closer {
+                        closest = Some((distance, order, other));
+                    }
                 }
             }
         }
-        if min_distance > 400.0 {
-            self.target = None;
-        }
+        closest.map(|(_, _, other)| other)
+    }
+}
 
-        if let Some(target) = self.target {
-            self.position += (target - self.position).normalize() * 0.3;
-        }
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+    use macroquad::rand;
+
+    use super::*;
+
+    /// Every position checked against every other, what the grid has to agree with.
+    fn closest_by_search(position: Vec2, others: &[Vec2]) -> Option<Vec2> {
+        others
+            .iter()
+            .map(|&other| (other, position.distance(other)))
+            .filter(|&(_, distance)| distance > MIN_DISTANCE && distance < MAX_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(other, _)| other)
     }
 
-    fn draw(&self, viewport: Rect) {
-        if viewport.contains(self.position) {
-            draw_poly(self.position.x, self.position.y, 10, 20.0, 0.0, DARKGRAY);
+    #[test]
+    fn targets_match_a_search_through_every_position() {
+        rand::srand(5);
+        let spread = MAX_DISTANCE * 4.0;
+        let positions: Vec<Vec2> = (0..300)
+            .map(|_| {
+                vec2(
+                    rand::gen_range(-spread, spread),
+                    rand::gen_range(-spread, spread),
+                )
+            })
+            .collect();
+        let neighbours: Vec<Vec2> = (0..100)
+            .map(|_| {
+                vec2(
+                    rand::gen_range(spread, spread * 2.0),
+                    rand::gen_range(0.0, spread),
+                )
+            })
+            .collect();
+        let mut behaviours = vec![Behaviour::Follower { target: None }; positions.len()];
+        let mut velocities = vec![Vec2::ZERO; positions.len()];
+        follow(
+            &positions,
+            &[&neighbours],
+            &mut behaviours,
+            &mut velocities,
+            1,
+        );
+
+        let everything: Vec<Vec2> = positions.iter().chain(&neighbours).copied().collect();
+        for (&position, behaviour) in positions.iter().zip(&behaviours) {
+            let Behaviour::Follower { target } = *behaviour else {
+                panic!("followers should stay followers");
+            };
+            assert_eq!(target, closest_by_search(position, &everything));
         }
     }
+
+    #[test]
+    fn equally_close_targets_go_to_the_first() {
+        let positions = [vec2(0.0, 0.0), vec2(100.0, 0.0), vec2(-100.0, 0.0)];
+        let mut behaviours = [Behaviour::Follower { target: None }; 3];
+        let mut velocities = [Vec2::ZERO; 3];
+        follow(&positions, &[], &mut behaviours, &mut velocities, 1);
+        assert_eq!(
+            behaviours.first(),
+            Some(&Behaviour::Follower {
+                target: Some(vec2(100.0, 0.0))
+            })
+        );
+    }
 }
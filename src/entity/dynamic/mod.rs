@@ -1,3 +1,135 @@
 pub mod follower;
 pub mod random_mover;
-pub mod updatable;
+pub mod store;
+
+use macroquad::color::DARKGRAY;
+use macroquad::math::{Rect, Vec2};
+use macroquad::shapes::draw_poly;
+
+use crate::entity::inspect::{Inspect, Property};
+
+/// How a dynamic entity is drawn, a regular polygon around its position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shape {
+    pub sides: u8,
+    pub size: f32,
+    /// In degrees.
+    pub rotation: f32,
+}
+
+impl Shape {
+    /// Axis-aligned box around the shape drawn at `position`, used for culling.
+    #[must_use]
+    pub fn bounds(&self, position: Vec2) -> Rect {
+        Rect::new(
+            position.x - self.size,
+            position.y - self.size,
+            self.size * 2.0,
+            self.size * 2.0,
+        )
+    }
+
+    pub fn draw(&self, position: Vec2) {
+        draw_poly(
+            position.x,
+            position.y,
+            self.sides,
+            self.size,
+            self.rotation,
+            DARKGRAY,
+        );
+    }
+}
+
+/// What sets a dynamic entity's velocity, every kind has its own system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Behaviour {
+    /// Wanders around, see [`random_mover::wander`].
+    RandomMover {
+        /// In degrees.
+        heading: f32,
+        speed: f32,
+    },
+    /// Walks to the closest entity nearby, see [`follower::follow`].
+    Follower { target: Option<Vec2> },
+}
+
+/// The components of one dynamic entity, copied out of a [`store::Dynamics`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dynamic {
+    pub position: Vec2,
+    /// How far it moves in the next update.
+    pub velocity: Vec2,
+    pub shape: Shape,
+    pub behaviour: Behaviour,
+}
+
+impl Dynamic {
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        self.shape.bounds(self.position)
+    }
+
+    /// Where the entity is heading, for the debug overlays.
+    #[must_use]
+    pub const fn target(&self) -> Option<Vec2> {
+        match self.behaviour {
+            Behaviour::Follower { target } => target,
+            Behaviour::RandomMover { .. } => None,
+        }
+    }
+
+    pub fn draw(&self) {
+        self.shape.draw(self.position);
+    }
+}
+
+impl Inspect for Dynamic {
+    fn kind(&self) -> &'static str {
+        match self.behaviour {
+            Behaviour::RandomMover { .. } => "random mover",
+            Behaviour::Follower { .. } => "follower",
+        }
+    }
+
+    fn properties(&self) -> Vec<Property> {
+        let mut properties = vec![
+            Property::number("x", self.position.x),
+            Property::number("y", self.position.y),
+        ];
+        match self.behaviour {
+            Behaviour::RandomMover { heading, speed } => properties.extend([
+                Property::number("rotation", heading),
+                Property::number("size", self.shape.size),
+                Property::number("speed", speed),
+            ]),
+            Behaviour::Follower { target } => {
+                let target = target.map_or_else(
+                    || "none".to_owned(),
+                    |target| format!("{:.0}, {:.0}", target.x, target.y),
+                );
+                properties.push(Property::text("target", target));
+            }
+        }
+        properties
+    }
+
+    fn set_property(&mut self, name: &str, value: f32) {
+        match (name, &mut self.behaviour) {
+            ("x", _) => self.position.x = value,
+            ("y", _) => self.position.y = value,
+            (
+                "rotation",
+                &mut Behaviour::RandomMover {
+                    ref mut heading, ..
+                },
+            ) => {
+                *heading = value;
+                self.shape.rotation = value;
+            }
+            ("size", &mut Behaviour::RandomMover { .. }) => self.shape.size = value.max(1.0),
+            ("speed", &mut Behaviour::RandomMover { ref mut speed, .. }) => *speed = value,
+            _ => (),
+        }
+    }
+}
@@ -1,52 +1,56 @@
-use macroquad::color::DARKGRAY;
-use macroquad::math::{vec2, Mat3, Rect, Vec2};
-use macroquad::rand;
-use macroquad::shapes::draw_poly;
+use macroquad::math::{vec2, Mat3, Vec2};
 
-use crate::entity::dynamic::updatable::Update;
-use crate::special::chunk::Chunk;
+use crate::common::Rng;
+use crate::entity::dynamic::{Behaviour, Dynamic, Shape};
 
-pub struct RandomMover {
-    position: Vec2,
-    rotation: f32,
-    size: f32,
-    speed: f32,
-}
+/// Degrees a random mover turns by at most in one tick.
+const MAX_TURN: f32 = 10.0;
 
-impl RandomMover {
-    #[must_use]
-    pub const fn new(position: Vec2, rotation: f32, size: f32, speed: f32) -> Self {
-        Self {
-            position,
-            rotation,
+#[must_use]
+pub const fn random_mover(position: Vec2, rotation: f32, size: f32, speed: f32) -> Dynamic {
+    Dynamic {
+        position,
+        velocity: Vec2::ZERO,
+        shape: Shape {
+            sides: 5,
             size,
+            rotation,
+        },
+        behaviour: Behaviour::RandomMover {
+            heading: rotation,
             speed,
-        }
+        },
     }
 }
 
-impl Update for RandomMover {
-    fn get_pos(&self) -> Vec2 {
-        self.position
-    }
-
-    fn update(&mut self, _chunk: &mut Chunk) {
-        self.rotation += rand::gen_range(-10., 10.);
-        let delta = vec2(0.0, self.speed);
-        let rotation_matrix = Mat3::from_rotation_z(self.rotation.to_radians());
-        self.position += rotation_matrix.transform_vector2(delta);
-    }
-
-    fn draw(&self, viewport: Rect) {
-        if viewport.contains(self.position) {
-            draw_poly(
-                self.position.x,
-                self.position.y,
-                5,
-                self.size,
-                self.rotation,
-                DARKGRAY,
-            );
+/// Turns every random mover a little and points its velocity where it is heading.
+///
+/// Movers are slowed down by the weather. Over a step of several `ticks` the turns add up
+/// like a random walk, by the square root of the ticks.
+pub fn wander(
+    rng: &mut Rng,
+    behaviours: &mut [Behaviour],
+    shapes: &mut [Shape],
+    velocities: &mut [Vec2],
+    speed_multiplier: f32,
+    ticks: u16,
+) {
+    let turn = MAX_TURN * f32::from(ticks).sqrt();
+    for ((behaviour, shape), velocity) in behaviours
+        .iter_mut()
+        .zip(shapes.iter_mut())
+        .zip(velocities.iter_mut())
+    {
+        if let Behaviour::RandomMover {
+            ref mut heading,
+            speed,
+        } = *behaviour
+        {
+            *heading += rng.range_f32(-turn, turn);
+            shape.rotation = *heading;
+            let delta = vec2(0.0, speed * speed_multiplier);
+            let rotation_matrix = Mat3::from_rotation_z(heading.to_radians());
+            *velocity = rotation_matrix.transform_vector2(delta);
         }
     }
 }
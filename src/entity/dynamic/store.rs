@@ -0,0 +1,229 @@
+use macroquad::math::{Rect, Vec2};
+
+use crate::entity::dynamic::{Behaviour, Dynamic, Shape};
+
+/// The components of a store as slices, so systems can borrow the ones they read and the
+/// ones they write at the same time. Entity `i` is at index `i` of every slice.
+pub struct Columns<'a> {
+    pub positions: &'a mut [Vec2],
+    pub velocities: &'a mut [Vec2],
+    pub shapes: &'a mut [Shape],
+    pub behaviours: &'a mut [Behaviour],
+}
+
+/// Dynamic entities stored as one array per component. Indices are only stable until
+/// entities are removed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dynamics {
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    shapes: Vec<Shape>,
+    behaviours: Vec<Behaviour>,
+}
+
+impl Dynamics {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            shapes: Vec::new(),
+            behaviours: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn push(&mut self, dynamic: Dynamic) {
+        self.positions.push(dynamic.position);
+        self.velocities.push(dynamic.velocity);
+        self.shapes.push(dynamic.shape);
+        self.behaviours.push(dynamic.behaviour);
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<Dynamic> {
+        Some(Dynamic {
+            position: *self.positions.get(index)?,
+            velocity: *self.velocities.get(index)?,
+            shape: *self.shapes.get(index)?,
+            behaviour: *self.behaviours.get(index)?,
+        })
+    }
+
+    /// Overwrites the entity at `index`, returns false if there is none.
+    pub fn set(&mut self, index: usize, dynamic: Dynamic) -> bool {
+        let (Some(position), Some(velocity), Some(shape), Some(behaviour)) = (
+            self.positions.get_mut(index),
+            self.velocities.get_mut(index),
+            self.shapes.get_mut(index),
+            self.behaviours.get_mut(index),
+        ) else {
+            return false;
+        };
+        *position = dynamic.position;
+        *velocity = dynamic.velocity;
+        *shape = dynamic.shape;
+        *behaviour = dynamic.behaviour;
+        true
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Dynamic> + '_ {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+
+    #[must_use]
+    pub fn positions(&self) -> &[Vec2] {
+        &self.positions
+    }
+
+    pub fn columns_mut(&mut self) -> Columns<'_> {
+        Columns {
+            positions: &mut self.positions,
+            velocities: &mut self.velocities,
+            shapes: &mut self.shapes,
+            behaviours: &mut self.behaviours,
+        }
+    }
+
+    /// Moves every entity by its velocity for `ticks` ticks.
+    pub fn apply_velocities(&mut self, ticks: u16) {
+        let ticks = f32::from(ticks);
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position += *velocity * ticks;
+        }
+    }
+
+    /// Takes out every entity whose position `leaves` is true for. The last entity takes
+    /// the place of each removed one, so it is linear but doesn't keep the order.
+    #[must_use]
+    pub fn extract(&mut self, mut leaves: impl FnMut(Vec2) -> bool) -> Self {
+        let mut extracted = Self::new();
+        let mut index = 0;
+        while let Some(&position) = self.positions.get(index) {
+            if leaves(position) {
+                extracted.push(self.swap_remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        extracted
+    }
+
+    /// Empties the store, oldest entity first.
+    pub fn drain(&mut self) -> impl Iterator<Item = Dynamic> + '_ {
+        self.positions
+            .drain(..)
+            .zip(self.velocities.drain(..))
+            .zip(self.shapes.drain(..))
+            .zip(self.behaviours.drain(..))
+            .map(|(((position, velocity), shape), behaviour)| Dynamic {
+                position,
+                velocity,
+                shape,
+                behaviour,
+            })
+    }
+
+    /// Around every entity's shape, `None` when there are no entities.
+    #[must_use]
+    pub fn bounds(&self) -> Option<Rect> {
+        self.positions
+            .iter()
+            .zip(&self.shapes)
+            .map(|(&position, shape)| shape.bounds(position))
+            .reduce(Rect::combine_with)
+    }
+
+    fn swap_remove(&mut self, index: usize) -> Dynamic {
+        Dynamic {
+            position: self.positions.swap_remove(index),
+            velocity: self.velocities.swap_remove(index),
+            shape: self.shapes.swap_remove(index),
+            behaviour: self.behaviours.swap_remove(index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::entity::dynamic::follower::{follow, follower};
+    use crate::entity::dynamic::random_mover::random_mover;
+
+    fn store(positions: &[Vec2]) -> Dynamics {
+        let mut store = Dynamics::new();
+        for &position in positions {
+            store.push(random_mover(position, 0.0, 10.0, 1.0));
+        }
+        store
+    }
+
+    #[test]
+    fn extract_takes_out_every_leaver_and_keeps_the_rest() {
+        let positions: Vec<Vec2> = (0..20_u8).map(|i| vec2(f32::from(i), 0.0)).collect();
+        let mut store = store(&positions);
+        let extracted = store.extract(|position| position.x % 3.0 == 0.0);
+
+        let mut left: Vec<f32> = store.positions().iter().map(|p| p.x).collect();
+        let mut taken: Vec<f32> = extracted.positions().iter().map(|p| p.x).collect();
+        left.sort_by(f32::total_cmp);
+        taken.sort_by(f32::total_cmp);
+        assert_eq!(taken, [0.0, 3.0, 6.0, 9.0, 12.0, 15.0, 18.0]);
+        assert_eq!(left.len() + taken.len(), positions.len());
+        assert!(left.iter().all(|x| x % 3.0 != 0.0));
+        // Every column moved along with the position.
+        for dynamic in store.iter().chain(extracted.iter()) {
+            assert!((dynamic.shape.size - 10.0).abs() < f32::EPSILON);
+        }
+    }
+
+    #[test]
+    fn entities_round_trip_through_the_store() {
+        let mut store = store(&[vec2(1.0, 2.0)]);
+        store.push(follower(vec2(5.0, 5.0)));
+        let mut follower = store.get(1).expect("pushed two entities");
+        assert_eq!(follower.position, vec2(5.0, 5.0));
+
+        follower.position = vec2(-1.0, -1.0);
+        assert!(store.set(1, follower));
+        assert!(!store.set(2, follower));
+        assert_eq!(store.get(1), Some(follower));
+        assert_eq!(store.drain().count(), 2);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn followers_head_for_the_closest_entity_in_sight() {
+        let mut store = Dynamics::new();
+        store.push(follower(vec2(0.0, 0.0)));
+        store.push(random_mover(vec2(300.0, 0.0), 0.0, 10.0, 1.0));
+        store.push(random_mover(vec2(0.0, 100.0), 0.0, 10.0, 1.0));
+        // Too close and too far away.
+        store.push(random_mover(vec2(10.0, 0.0), 0.0, 10.0, 1.0));
+        store.push(random_mover(vec2(1000.0, 0.0), 0.0, 10.0, 1.0));
+        let columns = store.columns_mut();
+        follow(
+            columns.positions,
+            &[],
+            columns.behaviours,
+            columns.velocities,
+            1,
+        );
+        store.apply_velocities(1);
+
+        let follower = store.get(0).expect("pushed first");
+        assert_eq!(follower.target(), Some(vec2(0.0, 100.0)));
+        assert!((follower.position - vec2(0.0, 0.3)).length() < 1e-6);
+    }
+}
@@ -0,0 +1,38 @@
+/// One line in the entity inspector.
+pub struct Property {
+    pub name: &'static str,
+    pub value: PropertyValue,
+}
+
+pub enum PropertyValue {
+    /// Editable in the inspector.
+    Number(f32),
+    /// Shown as it is.
+    Text(String),
+}
+
+impl Property {
+    #[must_use]
+    pub const fn number(name: &'static str, value: f32) -> Self {
+        Self {
+            name,
+            value: PropertyValue::Number(value),
+        }
+    }
+
+    #[must_use]
+    pub const fn text(name: &'static str, value: String) -> Self {
+        Self {
+            name,
+            value: PropertyValue::Text(value),
+        }
+    }
+}
+
+/// Lets the entity inspector show and edit an entity while the game runs.
+pub trait Inspect {
+    fn kind(&self) -> &'static str;
+    fn properties(&self) -> Vec<Property>;
+    /// Sets a `Number` property listed by `properties`, other names are ignored.
+    fn set_property(&mut self, name: &str, value: f32);
+}
@@ -1,2 +1,3 @@
 pub mod dynamic;
+pub mod inspect;
 pub mod statich;
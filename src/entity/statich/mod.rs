@@ -4,6 +4,10 @@ pub mod terrain;
 
 use std::cmp::{Eq, Ord, Ordering, PartialEq};
 
+use macroquad::math::{Rect, Vec2};
+
+use crate::entity::inspect::Inspect;
+
 #[derive(Debug)]
 pub enum Static {
     Stone(stone::Stone),
@@ -11,12 +15,50 @@ pub enum Static {
     Terrain(terrain::Terrain),
 }
 
+impl Static {
+    #[must_use]
+    pub fn position(&self) -> Vec2 {
+        match *self {
+            Self::Stone(ref stone) => stone.position(),
+            Self::Road(ref segment) => segment.endpoints().0,
+            Self::Terrain(ref terrain) => terrain.position(),
+        }
+    }
+
+    /// Axis-aligned box around what the static covers.
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        match *self {
+            Self::Stone(ref stone) => stone.bounds(),
+            Self::Road(ref segment) => segment.bounds(),
+            Self::Terrain(ref terrain) => terrain.bounds(),
+        }
+    }
+
+    #[must_use]
+    pub fn inspect(&self) -> &dyn Inspect {
+        match *self {
+            Self::Stone(ref stone) => stone,
+            Self::Road(ref segment) => segment,
+            Self::Terrain(ref terrain) => terrain,
+        }
+    }
+
+    pub fn inspect_mut(&mut self) -> &mut dyn Inspect {
+        match *self {
+            Self::Stone(ref mut stone) => stone,
+            Self::Road(ref mut segment) => segment,
+            Self::Terrain(ref mut terrain) => terrain,
+        }
+    }
+}
+
 impl PartialEq for Static {
     fn eq(&self, other: &Self) -> bool {
-        match self {
-            Self::Stone(_) => matches!(other, Self::Stone(_)),
-            Self::Road(_) => matches!(other, Self::Road(_)),
-            Self::Terrain(_) => matches!(other, Self::Terrain(_)),
+        match *self {
+            Self::Stone(_) => matches!(*other, Self::Stone(_)),
+            Self::Road(_) => matches!(*other, Self::Road(_)),
+            Self::Terrain(_) => matches!(*other, Self::Terrain(_)),
         }
     }
 }
@@ -25,17 +67,17 @@ impl Eq for Static {}
 
 impl Ord for Static {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self {
-            Self::Stone(_) => match other {
+        match *self {
+            Self::Stone(_) => match *other {
                 Self::Stone(_) => Ordering::Equal,
                 _ => Ordering::Greater,
             },
-            Self::Road(_) => match other {
+            Self::Road(_) => match *other {
                 Self::Road(_) => Ordering::Equal,
                 Self::Stone(_) => Ordering::Less,
                 Self::Terrain(_) => Ordering::Greater,
             },
-            Self::Terrain(_) => match other {
+            Self::Terrain(_) => match *other {
                 Self::Terrain(_) => Ordering::Equal,
                 _ => Ordering::Less,
             },
@@ -1,6 +1,9 @@
 use macroquad::color::DARKGRAY;
 use macroquad::math::{Mat3, Rect, Vec2};
-use macroquad::shapes::draw_line;
+
+use crate::entity::inspect::{Inspect, Property};
+use crate::special::batch::MeshBatch;
+use crate::special::export::Canvas;
 
 #[derive(Debug, PartialEq, PartialOrd)]
 pub struct Segment {
@@ -24,17 +27,52 @@ impl Segment {
         rotation_matrix.transform_point2(Vec2::new(0.0, self.size))
     }
 
-    pub fn draw(&self, viewport: Rect) {
-        if viewport.contains(self.position) {
-            let end = self.position + self.end();
-            draw_line(
-                self.position.x,
-                self.position.y,
-                end.x,
-                end.y,
-                self.size / 5.0,
-                DARKGRAY,
-            );
+    #[must_use]
+    pub fn endpoints(&self) -> (Vec2, Vec2) {
+        (self.position, self.position + self.end())
+    }
+
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        let (start, end) = self.endpoints();
+        let half_thickness = self.size / 10.0;
+        let min = start.min(end) - Vec2::splat(half_thickness);
+        let max = start.max(end) + Vec2::splat(half_thickness);
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    pub fn tessellate(&self, batch: &mut MeshBatch) {
+        let (start, end) = self.endpoints();
+        batch.add_line(start, end, self.size / 5.0, DARKGRAY);
+    }
+
+    pub fn rasterize(&self, canvas: &mut Canvas) {
+        let (start, end) = self.endpoints();
+        canvas.draw_line(start, end, self.size / 5.0, DARKGRAY);
+    }
+}
+
+impl Inspect for Segment {
+    fn kind(&self) -> &'static str {
+        "road segment"
+    }
+
+    fn properties(&self) -> Vec<Property> {
+        vec![
+            Property::number("x", self.position.x),
+            Property::number("y", self.position.y),
+            Property::number("rotation", self.rotation),
+            Property::number("size", self.size),
+        ]
+    }
+
+    fn set_property(&mut self, name: &str, value: f32) {
+        match name {
+            "x" => self.position.x = value,
+            "y" => self.position.y = value,
+            "rotation" => self.rotation = value,
+            "size" => self.size = value.max(1.0),
+            _ => (),
         }
     }
 }
@@ -1,6 +1,9 @@
 use macroquad::color::DARKGRAY;
 use macroquad::math::{Rect, Vec2};
-use macroquad::shapes::draw_poly;
+
+use crate::entity::inspect::{Inspect, Property};
+use crate::special::batch::MeshBatch;
+use crate::special::export::Canvas;
 
 #[derive(Debug, PartialEq, PartialOrd)]
 pub struct Stone {
@@ -19,16 +22,51 @@ impl Stone {
         }
     }
 
-    pub fn draw(&self, viewport: Rect) {
-        if viewport.contains(self.position) {
-            draw_poly(
-                self.position.x,
-                self.position.y,
-                5,
-                self.size,
-                self.rotation,
-                DARKGRAY,
-            );
+    #[must_use]
+    pub const fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        Rect::new(
+            self.position.x - self.size,
+            self.position.y - self.size,
+            self.size * 2.0,
+            self.size * 2.0,
+        )
+    }
+
+    pub fn tessellate(&self, batch: &mut MeshBatch) {
+        batch.add_polygon(self.position, 5, self.size, self.rotation, DARKGRAY);
+    }
+
+    pub fn rasterize(&self, canvas: &mut Canvas) {
+        canvas.fill_circle(self.position, self.size, DARKGRAY);
+    }
+}
+
+impl Inspect for Stone {
+    fn kind(&self) -> &'static str {
+        "stone"
+    }
+
+    fn properties(&self) -> Vec<Property> {
+        vec![
+            Property::number("x", self.position.x),
+            Property::number("y", self.position.y),
+            Property::number("rotation", self.rotation),
+            Property::number("size", self.size),
+        ]
+    }
+
+    fn set_property(&mut self, name: &str, value: f32) {
+        match name {
+            "x" => self.position.x = value,
+            "y" => self.position.y = value,
+            "rotation" => self.rotation = value,
+            "size" => self.size = value.max(1.0),
+            _ => (),
         }
     }
 }
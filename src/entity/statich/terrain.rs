@@ -1,7 +1,41 @@
 use macroquad::color::Color;
 use macroquad::color_u8;
-use macroquad::math::{Rect, Vec2};
-use macroquad::shapes::draw_rectangle;
+use macroquad::math::Rect;
+use macroquad::math::Vec2;
+
+use crate::common::{lerp, norm};
+use crate::entity::inspect::{Inspect, Property};
+use crate::special::export::Canvas;
+
+/// Noise values where a biome starts and its colour, in ascending order. The bands
+/// match the ones `Chunk::populate` uses to place stones and creatures.
+const BIOMES: [(f32, Color); 4] = [
+    (0.0, color_u8!(185, 192, 196, 255)),
+    (50.0, color_u8!(180, 208, 200, 255)),
+    (100.0, color_u8!(178, 226, 214, 255)),
+    (200.0, color_u8!(190, 246, 232, 255)),
+];
+/// How far on each side of a biome border, in noise values, the colours are blended.
+const BIOME_BLEND: f32 = 12.0;
+
+/// Terrain colour for a noise value in the range 0-255, blended smoothly between biomes.
+#[must_use]
+pub fn terrain_color(noise_value: f32) -> Color {
+    let mut color = BIOMES[0].1;
+    for &(start, biome) in &BIOMES[1..] {
+        let t = norm(noise_value, start - BIOME_BLEND, start + BIOME_BLEND).clamp(0.0, 1.0);
+        let t = t * t * 2.0_f32.mul_add(-t, 3.0);
+        color = Color::new(
+            lerp(color.r, biome.r, t),
+            lerp(color.g, biome.g, t),
+            lerp(color.b, biome.b, t),
+            1.0,
+        );
+    }
+    // A little shading inside each biome, so slopes stay readable.
+    let shade = lerp(0.94, 1.0, (noise_value / 255.0).clamp(0.0, 1.0));
+    Color::new(color.r * shade, color.g * shade, color.b * shade, 1.0)
+}
 
 #[derive(Debug, PartialEq, PartialOrd)]
 pub struct Terrain {
@@ -20,15 +54,45 @@ impl Terrain {
         }
     }
 
-    pub fn draw(&self, viewport: Rect) {
-        if viewport.contains(self.position) {
-            draw_rectangle(
-                self.position.x,
-                self.position.y,
-                self.size,
-                self.size,
-                color_u8!(0.0, self.color, 50.0 + self.color / 2.0, 70),
-            );
-        }
+    #[must_use]
+    pub fn color(&self) -> Color {
+        terrain_color(self.color)
     }
+
+    #[must_use]
+    pub const fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.position.x, self.position.y, self.size, self.size)
+    }
+
+    pub fn rasterize(&self, canvas: &mut Canvas) {
+        canvas.fill_rect(
+            Rect::new(self.position.x, self.position.y, self.size, self.size),
+            self.color(),
+        );
+    }
+}
+
+/// Terrain is drawn from the chunk's heightfield, so there is nothing to edit here.
+impl Inspect for Terrain {
+    fn kind(&self) -> &'static str {
+        "terrain"
+    }
+
+    fn properties(&self) -> Vec<Property> {
+        vec![
+            Property::text(
+                "position",
+                format!("{:.0}, {:.0}", self.position.x, self.position.y),
+            ),
+            Property::text("size", format!("{:.0}", self.size)),
+            Property::text("noise", format!("{:.1}", self.color)),
+        ]
+    }
+
+    fn set_property(&mut self, _name: &str, _value: f32) {}
 }
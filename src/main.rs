@@ -1,11 +1,9 @@
 #![warn(
     clippy::pedantic,
     clippy::nursery,
-    clippy::cargo,
     clippy::unwrap_used,
     clippy::unwrap_in_result,
     clippy::unneeded_field_pattern,
-    clippy::string_to_string,
     clippy::string_slice,
     clippy::string_add,
     clippy::str_to_string,
@@ -32,19 +30,109 @@
     clippy::verbose_file_reads
 )]
 #![allow(clippy::cast_precision_loss, clippy::missing_panics_doc)]
-use macroquad::window::next_frame;
+use std::process::ExitCode;
 
-pub mod common;
-pub mod entity;
-pub mod special;
-pub mod world;
+use macroquad::window::{clear_background, next_frame};
+use macroquad::Window;
 
-use special::profiler::profiler;
+use venturemmo::cli::{self, Command, ConfigOptions, PlayOptions};
+use venturemmo::special::bench::{format_summary, run_benchmark};
+use venturemmo::special::config::WorldConfig;
+use venturemmo::special::creation::WorldCreation;
+use venturemmo::special::export::export_region;
+use venturemmo::special::noise_graph::NoiseNode;
+use venturemmo::special::profiler::profiler;
+use venturemmo::world::{self, WorldSettings};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse(&args) {
+        Ok(Command::Play(options)) => {
+            let Some(config) = load_config(&options.config) else {
+                return ExitCode::FAILURE;
+            };
+            let noise_graph = match options.noise_graph {
+                Some(ref path) => match NoiseNode::load(path) {
+                    Ok(noise_graph) => noise_graph,
+                    Err(error) => {
+                        eprintln!("{error}");
+                        return ExitCode::FAILURE;
+                    }
+                },
+                None => NoiseNode::default(),
+            };
+            Window::new("game", game(options, noise_graph, config));
+            ExitCode::SUCCESS
+        }
+        Ok(Command::Export(options)) => {
+            let Some(config) = load_config(&options.config) else {
+                return ExitCode::FAILURE;
+            };
+            match export_region(&options, &config) {
+                Ok(()) => {
+                    println!("Exported {}", options.output);
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("{error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Ok(Command::Bench(options)) => {
+            let Some(config) = load_config(&options.config) else {
+                return ExitCode::FAILURE;
+            };
+            let report = run_benchmark(&options, &config);
+            println!("{}", format_summary(&report));
+            match report.write(&options.output) {
+                Ok(()) => {
+                    println!("Wrote {}", options.output);
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("{error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Err(error) => {
+            eprintln!("{}\n{}", error, cli::USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the error and returns `None` if the config can't be loaded.
+fn load_config(options: &ConfigOptions) -> Option<WorldConfig> {
+    WorldConfig::resolve(options.path.as_deref(), &options.overrides)
+        .map_err(|error| eprintln!("{error}"))
+        .ok()
+}
 
 #[allow(clippy::future_not_send)]
-#[macroquad::main("game")]
-async fn main() {
-    let mut world = world::World::new();
+async fn game(options: PlayOptions, noise_graph: NoiseNode, config: WorldConfig) {
+    let settings = WorldSettings {
+        noise_frequency: options.noise_frequency.unwrap_or(config.noise_frequency),
+        ..WorldSettings::default()
+    };
+    let settings = if let Some(seed) = options.seed {
+        WorldSettings { seed, ..settings }
+    } else {
+        let mut creation = WorldCreation::new(settings, noise_graph.clone(), config);
+        loop {
+            clear_background(macroquad::color::BLACK);
+            if let Some(settings) = creation.ui() {
+                break settings;
+            }
+            next_frame().await;
+        }
+    };
+
+    let mut world = world::World::with_settings(settings);
+    world.set_noise_graph(noise_graph, options.noise_graph.clone());
+    world.set_config(config);
+    world.set_resize_policy(options.resize_policy);
     world.setup();
 
     loop {
@@ -0,0 +1,151 @@
+use std::f32::consts::TAU;
+
+use macroquad::color::Color;
+use macroquad::math::{vec2, vec3, Rect, Vec2};
+use macroquad::models::{draw_mesh, Mesh, Vertex};
+
+/// macroquad 0.3 draws fewer vertices and indices than these per call, anything past them is
+/// cut off with only a warning.
+pub const MAX_MESH_VERTICES: usize = 10_000;
+pub const MAX_MESH_INDICES: usize = 5_000;
+
+/// Shapes collected into as few meshes as a draw call can hold, so drawing costs one
+/// `draw_mesh` call per mesh however many shapes there are.
+pub struct MeshBatch {
+    meshes: Vec<Mesh>,
+    bounds: Option<Rect>,
+}
+
+impl MeshBatch {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            meshes: Vec::new(),
+            bounds: None,
+        }
+    }
+
+    /// A filled regular polygon, like `draw_poly`. `rotation` is in degrees.
+    pub fn add_polygon(
+        &mut self,
+        center: Vec2,
+        sides: u8,
+        radius: f32,
+        rotation: f32,
+        color: Color,
+    ) {
+        let rotation = rotation.to_radians();
+        let mut points = Vec::with_capacity(usize::from(sides) + 1);
+        points.push(center);
+        let mut indices = Vec::with_capacity(usize::from(sides) * 3);
+        for i in 0..u16::from(sides) {
+            let angle = (f32::from(i) / f32::from(sides)).mul_add(TAU, rotation);
+            points.push(center + vec2(angle.cos(), angle.sin()) * radius);
+            indices.extend_from_slice(&[0, i + 1, (i + 1) % u16::from(sides) + 1]);
+        }
+        self.add(&points, &indices, color);
+    }
+
+    /// A line `thickness` wide, like `draw_line`.
+    pub fn add_line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        let direction = end - start;
+        if direction.length_squared() < f32::EPSILON {
+            return;
+        }
+        let normal = vec2(-direction.y, direction.x).normalize() * thickness / 2.0;
+        self.add(
+            &[start + normal, start - normal, end + normal, end - normal],
+            &[0, 1, 2, 2, 1, 3],
+            color,
+        );
+    }
+
+    /// Appends a shape, `indices` are relative to its own `points`.
+    fn add(&mut self, points: &[Vec2], indices: &[u16], color: Color) {
+        let room = |mesh: &Mesh| {
+            let fits = mesh.vertices.len() + points.len() < MAX_MESH_VERTICES
+                && mesh.indices.len() + indices.len() < MAX_MESH_INDICES;
+            fits.then(|| u16::try_from(mesh.vertices.len()).ok())
+                .flatten()
+        };
+        let first = if let Some(first) = self.meshes.last().and_then(room) {
+            first
+        } else {
+            self.meshes.push(Mesh {
+                vertices: Vec::new(),
+                indices: Vec::new(),
+                texture: None,
+            });
+            0
+        };
+        for point in points {
+            let point = Rect::new(point.x, point.y, 0.0, 0.0);
+            self.bounds = Some(
+                self.bounds
+                    .map_or(point, |bounds| bounds.combine_with(point)),
+            );
+        }
+        let mesh = self
+            .meshes
+            .last_mut()
+            .expect("there should be a mesh with room for the shape");
+        mesh.vertices.extend(points.iter().map(|point| Vertex {
+            position: vec3(point.x, point.y, 0.0),
+            uv: vec2(0.0, 0.0),
+            color,
+        }));
+        mesh.indices
+            .extend(indices.iter().map(|index| first + index));
+    }
+
+    /// Axis-aligned box around every shape, `None` while the batch is empty.
+    #[must_use]
+    pub const fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+
+    #[must_use]
+    pub fn vertex_count(&self) -> usize {
+        self.meshes.iter().map(|mesh| mesh.vertices.len()).sum()
+    }
+
+    pub fn draw(&self) {
+        for mesh in &self.meshes {
+            draw_mesh(mesh);
+        }
+    }
+}
+
+impl Default for MeshBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::WHITE;
+
+    use super::*;
+
+    #[test]
+    fn meshes_stay_within_a_draw_call() {
+        let mut batch = MeshBatch::new();
+        for i in 0..1_000_u16 {
+            let center = vec2(f32::from(i), 0.0);
+            batch.add_polygon(center, 5, 3.0, 0.0, WHITE);
+            batch.add_line(center, center + vec2(0.0, 10.0), 1.0, WHITE);
+        }
+        let indices: usize = batch.meshes.iter().map(|mesh| mesh.indices.len()).sum();
+        assert_eq!(indices, 1_000 * (5 * 3 + 6));
+        assert!(batch.meshes.len() > 1);
+        for mesh in &batch.meshes {
+            assert!(mesh.vertices.len() < MAX_MESH_VERTICES);
+            assert!(mesh.indices.len() < MAX_MESH_INDICES);
+            assert!(mesh
+                .indices
+                .iter()
+                .all(|&index| usize::from(index) < mesh.vertices.len()));
+        }
+    }
+}
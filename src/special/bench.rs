@@ -0,0 +1,67 @@
+use macroquad::math::vec2;
+use macroquad::rand;
+
+use crate::cli::BenchOptions;
+use crate::special::config::WorldConfig;
+use crate::special::zones::{self, Report};
+use crate::world::{World, WorldSettings};
+
+/// How many frames the player takes to walk across a chunk, so new chunks keep being
+/// generated during the run.
+const FRAMES_PER_CHUNK: f32 = 60.0;
+
+/// Runs the simulation without a window and records the profiler zones of every frame.
+/// Setting up the world is the first frame. Drawing needs a window, so it isn't part of
+/// the report.
+#[must_use]
+pub fn run_benchmark(options: &BenchOptions, config: &WorldConfig) -> Report {
+    rand::srand(options.seed);
+    zones::start_recording();
+
+    let mut world = World::headless(WorldSettings {
+        seed: options.seed,
+        noise_frequency: config.noise_frequency,
+    });
+    world.set_config(*config);
+    world.setup();
+    zones::finish_frame();
+
+    let step = vec2(config.chunk_world_size() / FRAMES_PER_CHUNK, 0.0);
+    for _ in 0..options.frames {
+        world.move_player(step);
+        world.simulate();
+        zones::finish_frame();
+    }
+    zones::stop_recording()
+}
+
+/// A table of the time spent in each zone, in milliseconds.
+#[must_use]
+pub fn format_summary(report: &Report) -> String {
+    let frames = u32::try_from(report.frames.len()).unwrap_or(u32::MAX);
+    let total: f64 = report.frames.iter().map(|frame| frame.frame_time).sum();
+    let header = format!(
+        "{} frames, {:.3}ms per frame\n{:<40} {:>7} {:>11} {:>9} {:>9}",
+        frames,
+        total * 1000.0 / f64::from(frames.max(1)),
+        "zone",
+        "count",
+        "total ms",
+        "mean ms",
+        "max ms"
+    );
+    let rows = report.summary().into_iter().map(|zone| {
+        format!(
+            "{:<40} {:>7} {:>11.3} {:>9.3} {:>9.3}",
+            zone.path,
+            zone.count,
+            zone.total * 1000.0,
+            zone.total * 1000.0 / f64::from(zone.count),
+            zone.max * 1000.0
+        )
+    });
+    std::iter::once(header)
+        .chain(rows)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
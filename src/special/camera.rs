@@ -1,53 +1,216 @@
 use std::default::Default;
+use std::f32::consts::{PI, TAU};
 
-use macroquad::input::{is_key_down, mouse_position, KeyCode};
-use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::input::{is_key_down, mouse_position, mouse_wheel, KeyCode};
+use macroquad::math::{vec2, Mat3, Rect, Vec2};
+use macroquad::rand;
 use macroquad::window::{screen_height, screen_width};
 
-use crate::common::map;
+use crate::special::frustum::ViewFrustum;
+
+/// How fast the camera turns towards the followed heading, higher is snappier.
+const HEADING_FOLLOW_RATE: f32 = 4.0;
+/// Roughly how many seconds the camera takes to catch up with what it follows.
+const FOLLOW_SMOOTH_TIME: f32 = 0.3;
+/// How many seconds ahead of the followed entity's movement the camera looks.
+const LOOK_AHEAD_TIME: f32 = 0.5;
+/// Zoom limits, zoom is screen halves per world unit so these are roughly 80 000 world
+/// units across the screen at most and 200 at least.
+const MIN_ZOOM: f32 = 1.0 / 40_000.0;
+const MAX_ZOOM: f32 = 1.0 / 100.0;
+/// Zoom factor per wheel step.
+const WHEEL_ZOOM: f32 = 1.1;
+/// Zoom factor per second while a zoom key is held.
+const KEY_ZOOM: f32 = 3.0;
+/// Fraction of the view panned per second while a pan key is held.
+const PAN_SPEED: f32 = 0.3;
+/// Largest shake offset as a fraction of the view size.
+const MAX_SHAKE: f32 = 0.02;
+/// Shake trauma lost per second.
+const SHAKE_DECAY: f32 = 1.5;
+
+/// What happens to the view when the window changes size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ResizePolicy {
+    /// World units per pixel stay the same, a bigger window shows more of the world.
+    #[default]
+    ConstantScale,
+    /// The world shown across the shorter side of the window stays the same.
+    Fit,
+}
+
+/// Whether the camera stays north-up or turns with what it follows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    NorthUp,
+    HeadingUp,
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Camera {
     pub target: Vec2,
+    /// In radians, the view turns by this much around `target`.
     pub rotation: f32,
     pub zoom: Vec2,
+    pub mode: CameraMode,
+    pub resize_policy: ResizePolicy,
     pub followed_pos: Option<Vec2>,
     pub followed_rot: Option<f32>,
+
+    /// How fast `target` moves while following.
+    velocity: Vec2,
+    /// Estimated from how `followed_pos` changes between updates, for the look-ahead.
+    followed_velocity: Vec2,
+    last_followed_pos: Option<Vec2>,
+    /// 0 to 1, the shake is the square of it.
+    trauma: f32,
+    shake_offset: Vec2,
+    /// Window size in pixels the zoom was last fitted to.
+    screen_size: Vec2,
 }
 
 impl Camera {
     #[must_use]
     pub fn new() -> Self {
-        let starting_zoom = 1.0 / screen_width();
+        Self::with_screen_size(vec2(screen_width(), screen_height()))
+    }
+
+    /// A camera for a window of `screen_size` pixels, showing two world units per pixel.
+    #[must_use]
+    pub fn with_screen_size(screen_size: Vec2) -> Self {
+        let starting_zoom = 1.0 / screen_size.x;
         Self {
             target: vec2(0.0, 0.0),
             rotation: 0.0,
-            zoom: vec2(
-                starting_zoom,
-                starting_zoom * screen_width() / screen_height(),
-            ),
+            zoom: vec2(starting_zoom, starting_zoom * screen_size.x / screen_size.y),
+            mode: CameraMode::NorthUp,
+            resize_policy: ResizePolicy::default(),
             followed_pos: None,
             followed_rot: None,
+            velocity: Vec2::ZERO,
+            followed_velocity: Vec2::ZERO,
+            last_followed_pos: None,
+            trauma: 0.0,
+            shake_offset: Vec2::ZERO,
+            screen_size,
         }
     }
 
-    pub fn update(&mut self) {
-        if let Some(target) = self.followed_pos {
-            self.target = target;
+    /// Fits the zoom to a new window size following `resize_policy`, call it every frame.
+    pub fn resize(&mut self, screen_size: Vec2) {
+        if screen_size == self.screen_size || screen_size.min_element() <= 0.0 {
+            return;
         }
-        if let Some(rot) = self.followed_rot {
-            self.rotation = rot;
+        let units_per_pixel = 2.0 / (self.zoom.x * self.screen_size.x);
+        let units_per_pixel = match self.resize_policy {
+            ResizePolicy::ConstantScale => units_per_pixel,
+            ResizePolicy::Fit => {
+                units_per_pixel * self.screen_size.min_element() / screen_size.min_element()
+            }
+        };
+        self.zoom = vec2(2.0, 2.0) / (screen_size * units_per_pixel);
+        self.screen_size = screen_size;
+    }
+
+    #[must_use]
+    pub const fn screen_size(&self) -> Vec2 {
+        self.screen_size
+    }
+
+    /// `delta` is the frame time in seconds.
+    pub fn update(&mut self, delta: f32) {
+        if delta <= 0.0 {
+            return;
         }
+        if let Some(followed) = self.followed_pos {
+            if let Some(last) = self.last_followed_pos {
+                let measured = (followed - last) / delta;
+                let blend = 1.0 - (-10.0 * delta).exp();
+                self.followed_velocity += (measured - self.followed_velocity) * blend;
+            }
+            self.last_followed_pos = Some(followed);
+            let goal = followed + self.followed_velocity * LOOK_AHEAD_TIME;
+            self.smooth_follow(goal, delta);
+        } else {
+            self.last_followed_pos = None;
+            self.followed_velocity = Vec2::ZERO;
+            self.velocity = Vec2::ZERO;
+        }
+        self.update_shake(delta);
+
+        let wanted_rotation = match (self.mode, self.followed_rot) {
+            // The followed entity faces +y, which has to end up pointing up on screen.
+            (CameraMode::HeadingUp, Some(heading)) => heading + PI,
+            (CameraMode::HeadingUp, None) => self.rotation,
+            (CameraMode::NorthUp, _) => 0.0,
+        };
+        let turn = shortest_angle(wanted_rotation - self.rotation);
+        let follow = 1.0 - (-HEADING_FOLLOW_RATE * delta).exp();
+        self.rotation = turn.mul_add(follow, self.rotation).rem_euclid(TAU);
+    }
+
+    /// Critically damped spring towards `goal`, stable for any frame time.
+    fn smooth_follow(&mut self, goal: Vec2, delta: f32) {
+        let omega = 2.0 / FOLLOW_SMOOTH_TIME;
+        let x = omega * delta;
+        let decay = 1.0 / x.mul_add(x.mul_add(0.235_f32.mul_add(x, 0.48), 1.0), 1.0);
+        let change = self.target - goal;
+        let temp = (self.velocity + change * omega) * delta;
+        self.velocity = (self.velocity - temp * omega) * decay;
+        self.target = goal + (change + temp) * decay;
+    }
+
+    fn update_shake(&mut self, delta: f32) {
+        self.trauma = SHAKE_DECAY.mul_add(-delta, self.trauma).max(0.0);
+        let (width, height) = self.viewport_size();
+        let amount = self.trauma * self.trauma * MAX_SHAKE;
+        self.shake_offset = vec2(
+            rand::gen_range(-1.0, 1.0) * width * amount,
+            rand::gen_range(-1.0, 1.0) * height * amount,
+        );
     }
 
-    pub fn unfollow(&mut self) {
+    /// Shakes the view, `trauma` from 0 to 1 adds up and wears off over time.
+    pub fn shake(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    /// Where the view is centered, `target` plus the shake.
+    #[must_use]
+    pub fn center(&self) -> Vec2 {
+        self.target + self.shake_offset
+    }
+
+    /// Multiplies the zoom by `factor`, within the zoom limits.
+    pub fn zoom_by(&mut self, factor: f32) {
+        let zoom_x = (self.zoom.x * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.zoom *= zoom_x / self.zoom.x;
+    }
+
+    /// Zooms by `factor` keeping the world point under `screen_point` where it is.
+    pub fn zoom_at(&mut self, factor: f32, screen_point: Vec2) {
+        let before = self.screen_to_world(screen_point);
+        self.zoom_by(factor);
+        self.target += before - self.screen_to_world(screen_point);
+    }
+
+    pub const fn unfollow(&mut self) {
         self.followed_pos = None;
         self.followed_rot = None;
     }
 
-    pub fn set_follow(&mut self, position: Option<Vec2>, _rotation: Option<f32>) {
+    /// Follows `position`, and in heading-up mode turns so that `rotation`, the heading
+    /// of whatever is followed, points up on screen.
+    pub const fn set_follow(&mut self, position: Option<Vec2>, rotation: Option<f32>) {
         self.followed_pos = position;
-        //self.followed_rot = rotation;
+        self.followed_rot = rotation;
+    }
+
+    pub const fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::NorthUp => CameraMode::HeadingUp,
+            CameraMode::HeadingUp => CameraMode::NorthUp,
+        };
     }
 
     #[must_use]
@@ -55,42 +218,51 @@ impl Camera {
         (2.0 * (1.0 / self.zoom.x), 2.0 * (1.0 / self.zoom.y))
     }
 
+    /// Axis-aligned box around everything on screen, larger than `viewport_size` while
+    /// the camera is rotated.
     #[must_use]
     pub fn viewport_rect(&self) -> Rect {
+        self.view_frustum().bounds()
+    }
+
+    /// What the camera sees, taking its rotation into account.
+    #[must_use]
+    pub fn view_frustum(&self) -> ViewFrustum {
         let (viewport_width, viewport_height) = self.viewport_size();
-        Rect::new(
-            self.target.x - (viewport_width / 2.0),
-            self.target.y - (viewport_height / 2.0),
-            viewport_width,
-            viewport_height,
+        ViewFrustum::new(
+            self.center(),
+            vec2(viewport_width / 2.0, viewport_height / 2.0),
+            self.rotation,
         )
     }
 
     #[must_use]
     pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
-        let (half_width, half_height) = (screen_width() / 2.0, screen_height() / 2.0);
-        // x, y offset from the center of the window.
-        let (x, y) = ((point.x - half_width), (point.y - half_height));
+        let half_screen = self.screen_size / 2.0;
+        // Offset from the center of the window, in world units but still screen aligned.
+        let offset = (point - half_screen) / (half_screen * self.zoom);
+        self.center() + Mat3::from_rotation_z(self.rotation).transform_vector2(offset)
+    }
 
-        let (viewport_width, viewport_height) = self.viewport_size();
-        let (half_viewport_width, half_viewport_height) =
-            ((viewport_width / 2.0), (viewport_height / 2.0));
-
-        let result_x = map(
-            x,
-            -half_width,
-            half_width,
-            -half_viewport_width,
-            half_viewport_width,
-        );
-        let result_y = map(
-            y,
-            -half_height,
-            half_height,
-            -half_viewport_height,
-            half_viewport_height,
-        );
-        vec2(result_x + self.target.x, result_y + self.target.y)
+    /// Inverse of `screen_to_world`.
+    #[must_use]
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let half_screen = self.screen_size / 2.0;
+        let offset = Mat3::from_rotation_z(-self.rotation).transform_vector2(point - self.center());
+        half_screen + offset * self.zoom * half_screen
+    }
+
+    /// Axis-aligned box around where `rect` ends up on screen, the rect's corners are
+    /// turned with the camera.
+    #[must_use]
+    pub fn world_rect_to_screen(&self, rect: Rect) -> Rect {
+        bounding_rect(corners(rect).map(|corner| self.world_to_screen(corner)))
+    }
+
+    /// Axis-aligned box around the part of the world under `rect` on screen.
+    #[must_use]
+    pub fn screen_rect_to_world(&self, rect: Rect) -> Rect {
+        bounding_rect(corners(rect).map(|corner| self.screen_to_world(corner)))
     }
 
     #[must_use]
@@ -101,43 +273,172 @@ impl Camera {
     }
 }
 
+fn corners(rect: Rect) -> [Vec2; 4] {
+    [
+        vec2(rect.left(), rect.top()),
+        vec2(rect.right(), rect.top()),
+        vec2(rect.right(), rect.bottom()),
+        vec2(rect.left(), rect.bottom()),
+    ]
+}
+
+fn bounding_rect(points: [Vec2; 4]) -> Rect {
+    let min = points.iter().fold(points[0], |min, point| min.min(*point));
+    let max = points.iter().fold(points[0], |max, point| max.max(*point));
+    Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+/// `angle` wrapped into `-PI..PI`, the shortest way to turn by it.
+fn shortest_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
 impl Default for Camera {
     fn default() -> Self {
         Self::new()
     }
 }
 
-pub fn top_down_camera_controls(camera: &mut Camera) {
+/// Zooms towards the cursor with the mouse wheel.
+pub fn camera_zoom_controls(camera: &mut Camera) {
+    let (_, wheel) = mouse_wheel();
+    if wheel != 0.0 {
+        let factor = if wheel > 0.0 {
+            WHEEL_ZOOM
+        } else {
+            1.0 / WHEEL_ZOOM
+        };
+        let mouse = mouse_position();
+        camera.zoom_at(factor, vec2(mouse.0, mouse.1));
+    }
+}
+
+/// Free camera movement, `delta` is the frame time in seconds.
+pub fn top_down_camera_controls(camera: &mut Camera, delta: f32) {
+    let pan = PAN_SPEED * delta / camera.zoom.x;
     // scroll
     if is_key_down(KeyCode::Comma) {
         // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.y -= 0.01 / camera.zoom.x;
+        camera.target.y -= pan;
         camera.unfollow();
     }
     if is_key_down(KeyCode::O) {
         // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.y += 0.01 / camera.zoom.x;
+        camera.target.y += pan;
         camera.unfollow();
     }
     if is_key_down(KeyCode::A) {
         // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.x -= 0.01 / camera.zoom.x;
+        camera.target.x -= pan;
         camera.unfollow();
     }
     if is_key_down(KeyCode::E) {
         // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.x += 0.01 / camera.zoom.x;
+        camera.target.x += pan;
         camera.unfollow();
     }
     // zoom
     if is_key_down(KeyCode::PageUp) || is_key_down(KeyCode::Apostrophe) {
-        camera.zoom.x *= 0.98;
-        camera.zoom.y *= 0.98;
+        camera.zoom_by(KEY_ZOOM.powf(-delta));
         camera.unfollow();
     }
     if is_key_down(KeyCode::PageDown) || is_key_down(KeyCode::Period) {
-        camera.zoom.x /= 0.98;
-        camera.zoom.y /= 0.98;
+        camera.zoom_by(KEY_ZOOM.powf(delta));
         camera.unfollow();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn test_camera() -> Camera {
+        let mut camera = Camera::with_screen_size(vec2(800.0, 600.0));
+        camera.target = vec2(1000.0, -500.0);
+        camera
+    }
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 0.01, "{a} is not {b}");
+    }
+
+    #[test]
+    fn target_is_at_the_screen_center() {
+        let camera = test_camera();
+        assert_close(camera.world_to_screen(camera.target), vec2(400.0, 300.0));
+        assert_close(camera.screen_to_world(vec2(400.0, 300.0)), camera.target);
+    }
+
+    #[test]
+    fn zoom_scales_around_the_center() {
+        let mut camera = test_camera();
+        // Two world units per pixel.
+        assert_close(
+            camera.world_to_screen(camera.target + vec2(200.0, 100.0)),
+            vec2(500.0, 350.0),
+        );
+        camera.zoom_by(2.0);
+        assert_close(
+            camera.world_to_screen(camera.target + vec2(200.0, 100.0)),
+            vec2(600.0, 400.0),
+        );
+    }
+
+    #[test]
+    fn rotation_turns_the_view() {
+        let mut camera = test_camera();
+        camera.rotation = FRAC_PI_2;
+        // The world's +x points up on screen.
+        assert_close(
+            camera.world_to_screen(camera.target + vec2(200.0, 0.0)),
+            vec2(400.0, 200.0),
+        );
+    }
+
+    #[test]
+    fn points_round_trip() {
+        let mut camera = test_camera();
+        camera.rotation = 0.7;
+        camera.zoom_by(1.7);
+        for point in [vec2(0.0, 0.0), vec2(123.0, 456.0), vec2(799.0, 1.0)] {
+            assert_close(camera.world_to_screen(camera.screen_to_world(point)), point);
+        }
+        let world = vec2(-3000.0, 2500.0);
+        assert_close(camera.screen_to_world(camera.world_to_screen(world)), world);
+    }
+
+    #[test]
+    fn rects_cover_their_corners() {
+        let mut camera = test_camera();
+        camera.rotation = 0.3;
+        let screen = Rect::new(0.0, 0.0, 800.0, 600.0);
+        let world = camera.screen_rect_to_world(screen);
+        let viewport = camera.viewport_rect();
+        assert_close(vec2(world.x, world.y), vec2(viewport.x, viewport.y));
+        assert_close(vec2(world.w, world.h), vec2(viewport.w, viewport.h));
+        let back = camera.world_rect_to_screen(world);
+        assert!(back.w >= screen.w && back.h >= screen.h);
+        assert_close(
+            vec2(back.x + back.w / 2.0, back.y + back.h / 2.0),
+            vec2(400.0, 300.0),
+        );
+    }
+
+    #[test]
+    fn resizing_keeps_the_scale_or_fits() {
+        let mut camera = test_camera();
+        let before = camera.viewport_size();
+        camera.resize(vec2(1600.0, 600.0));
+        assert!(before.0.mul_add(-2.0, camera.viewport_size().0).abs() < 0.01);
+        assert!((camera.viewport_size().1 - before.1).abs() < 0.01);
+
+        let mut camera = Camera {
+            resize_policy: ResizePolicy::Fit,
+            ..test_camera()
+        };
+        camera.resize(vec2(1600.0, 1200.0));
+        assert!((camera.viewport_size().1 - before.1).abs() < 0.01);
+    }
+}
@@ -2,272 +2,661 @@ use macroquad::color::Color;
 use macroquad::color_u8;
 use macroquad::logging::{info, warn};
 use macroquad::math::{vec2, Rect, Vec2};
-use macroquad::rand;
-use macroquad::shapes::draw_rectangle;
-use macroquad::texture::{draw_texture, Image, Texture2D};
+use macroquad::models::{draw_mesh, Mesh};
+use macroquad::texture::{draw_texture_ex, DrawTextureParams, Texture2D};
 
 use once_cell::sync::OnceCell;
 
-use crate::common::map;
-use crate::entity::dynamic::follower::Follower;
-use crate::entity::dynamic::random_mover::RandomMover;
-use crate::entity::dynamic::updatable::Update;
+use crate::common::{floor_i32, Rng};
+use crate::entity::dynamic::follower::{follow, follower};
+use crate::entity::dynamic::random_mover::{random_mover, wander};
+use crate::entity::dynamic::store::Dynamics;
 use crate::entity::statich::road::Segment;
 use crate::entity::statich::stone::Stone;
-use crate::entity::statich::terrain::Terrain;
+use crate::entity::statich::terrain::{terrain_color, Terrain};
 use crate::entity::statich::Static;
+use crate::special::batch::MeshBatch;
+use crate::special::config::WorldConfig;
+use crate::special::export::Canvas;
+use crate::special::frustum::ViewFrustum;
+use crate::special::heightfield::Heightfield;
 use crate::special::noise::Noise;
+use crate::special::weather::WeatherSample;
+use crate::special::zones;
 
-use crate::world::{ChunkPosition, CHUNK_SIZE, CHUNK_TILE_SIZE, NOISE_IMAGE_SIZE};
+use crate::world::ChunkPosition;
+
+/// Longest catch-up a chunk does when it is simulated again, in steps.
+const MAX_CATCH_UP_STEPS: u64 = 32;
 
 pub struct Chunk {
-    chunk_position: ChunkPosition,
+    position: ChunkPosition,
+    config: WorldConfig,
 
-    pub dynamics: Vec<Option<Box<dyn Update>>>,
-    pub statics: Vec<Static>,
+    pub dynamics: Dynamics,
+    statics: Vec<Static>,
+    pub weather: WeatherSample,
+    /// World time the chunk was generated at, in seconds.
+    pub generated_at: f64,
+    /// World tick the chunk has been simulated up to.
+    pub simulated_until: u64,
+    /// Drives the simulation, continues from where generation left off so updates are
+    /// reproducible too.
+    rng: Rng,
 
-    noise_image: OnceCell<Image>,
+    heightfield: OnceCell<Heightfield>,
+    terrain_meshes: Vec<Mesh>,
+    /// Stones and roads, rebuilt the next time the chunk is drawn after they change.
+    statics_batch: OnceCell<MeshBatch>,
+    height_texture: OnceCell<Texture2D>,
     noise_texture: OnceCell<Texture2D>,
 }
 
 impl Chunk {
     #[must_use]
-    pub fn new(world_position: ChunkPosition) -> Self {
+    pub fn new(world_position: ChunkPosition, config: WorldConfig) -> Self {
         Self {
-            chunk_position: world_position,
-            dynamics: Vec::new(),
+            position: world_position,
+            config,
+            dynamics: Dynamics::new(),
             statics: Vec::new(),
-            noise_image: OnceCell::new(),
+            weather: WeatherSample::clear(),
+            generated_at: 0.0,
+            simulated_until: 0,
+            rng: Rng::new(0),
+            heightfield: OnceCell::new(),
+            terrain_meshes: Vec::new(),
+            statics_batch: OnceCell::new(),
+            height_texture: OnceCell::new(),
             noise_texture: OnceCell::new(),
         }
     }
 
     pub fn init(&mut self, noise: &Noise) {
-        let (xoff, yoff) = self.chunk_position.offsets(f32::from(NOISE_IMAGE_SIZE));
-        let image = Noise::gen_image(NOISE_IMAGE_SIZE, xoff, yoff, noise.get());
-        match self.noise_image.set(image) {
-            Ok(_) => info!("noise x: {}, noise y: {}", xoff, yoff),
-            Err(_) => warn!("Tried to reinit chunk"),
+        // One sample per cell corner, the last row and column are shared with the
+        // neighbouring chunks.
+        let origin = self.origin();
+        let config = &self.config;
+        let heightfield = {
+            let _zone = zones::zone(zones::NOISE_CREATION);
+            Heightfield::sample(
+                noise,
+                config,
+                config.chunk_size + 1,
+                origin,
+                config.chunk_tile_size,
+            )
+        };
+        let meshes = heightfield.to_meshes(origin, config.chunk_tile_size, terrain_color);
+        if self.heightfield.set(heightfield).is_ok() {
+            self.terrain_meshes = meshes;
+            info!("heightfield x: {}, y: {}", origin.x, origin.y);
+        } else {
+            warn!("Tried to reinit chunk");
         }
     }
 
+    /// Fills the chunk with terrain, stones and creatures. What it generates only depends
+    /// on the noise seed and the chunk's position, not on what was generated before.
     pub fn populate(&mut self, noise: &Noise) {
         self.init(noise);
+        let mut rng = self.rng(noise.seed());
 
-        let cells = CHUNK_SIZE as usize;
-        let cell_size = CHUNK_TILE_SIZE;
+        let cells = usize::from(self.config.chunk_size);
+        let cell_size = self.config.chunk_tile_size;
 
-        let (xoff, yoff) = self.chunk_position.offsets(cells as f32 * cell_size as f32);
+        let origin = self.origin();
+        let (xoff, yoff) = (origin.x, origin.y);
         info!(
             "xoff: {}, yoff: {}, cell_size: {}, cells: {}",
             xoff, yoff, cell_size, cells
         );
-        for y in 0..cells {
-            let pos_y = (y as f32).mul_add(cell_size, yoff);
-            for x in 0..cells {
-                let pos_x = (x as f32).mul_add(cell_size, xoff);
+        for y in 0..self.config.chunk_size {
+            let pos_y = f32::from(y).mul_add(cell_size, yoff);
+            for x in 0..self.config.chunk_size {
+                let pos_x = f32::from(x).mul_add(cell_size, xoff);
 
-                let noise_value = self.get_point(
-                    x as u32 * u32::from(NOISE_IMAGE_SIZE / CHUNK_SIZE),
-                    y as u32 * u32::from(NOISE_IMAGE_SIZE / CHUNK_SIZE),
-                );
+                let noise_value = self.get_point(x, y);
 
-                self.populate_cell(pos_x, pos_y, cell_size, noise_value);
+                self.populate_cell(&mut rng, pos_x, pos_y, cell_size, noise_value);
 
                 self.statics.push(Static::Terrain(Terrain::new(
-                    vec2(pos_x as f32, pos_y as f32),
+                    vec2(pos_x, pos_y),
                     noise_value,
-                    cell_size as f32,
+                    cell_size,
                 )));
             }
         }
         self.statics.sort_unstable();
+        self.statics_batch.take();
+        self.rng = rng;
     }
 
-    fn populate_cell(&mut self, x: f32, y: f32, cell_size: f32, noise_value: f32) {
+    fn populate_cell(&mut self, rng: &mut Rng, x: f32, y: f32, cell_size: f32, noise_value: f32) {
         let max_stone_size = 80.0;
-        let noise_value = noise_value as u8;
+        let noise_value = floor_i32(noise_value).map_or(0, |value| value.clamp(0, 255));
+        let noise_value = u8::try_from(noise_value).unwrap_or(u8::MAX);
         match noise_value {
             0..=49 => (),
             50..=99 => {
-                let stones = rand::gen_range(0, 1 + 1);
+                let stones = rng.range_u32(0, 1 + 1);
                 for _ in 0..stones {
-                    let pos_x = rand::gen_range(x, x + cell_size);
-                    let pos_y = rand::gen_range(y, y + cell_size);
+                    let pos_x = rng.range_f32(x, x + cell_size);
+                    let pos_y = rng.range_f32(y, y + cell_size);
                     self.statics.push(Static::Stone(Stone::new(
-                        vec2(pos_x as f32, pos_y as f32),
+                        vec2(pos_x, pos_y),
                         f32::from(noise_value) * 3.0,
-                        rand::gen_range(5.0, max_stone_size / 3.0) as f32,
+                        rng.range_f32(5.0, max_stone_size / 3.0),
                     )));
                 }
             }
             100..=199 => {
-                let stones = rand::gen_range(0, 2 + 1);
+                let stones = rng.range_u32(0, 2 + 1);
                 for _ in 0..stones {
-                    let pos_x = rand::gen_range(x, x + cell_size);
-                    let pos_y = rand::gen_range(y, y + cell_size);
+                    let pos_x = rng.range_f32(x, x + cell_size);
+                    let pos_y = rng.range_f32(y, y + cell_size);
                     self.statics.push(Static::Stone(Stone::new(
-                        vec2(pos_x as f32, pos_y as f32),
+                        vec2(pos_x, pos_y),
                         f32::from(noise_value) * 3.0,
-                        rand::gen_range(5.0, max_stone_size / 2.0) as f32,
+                        rng.range_f32(5.0, max_stone_size / 2.0),
                     )));
                 }
-                let random = rand::gen_range(0, 10);
+                let random = rng.range_u32(0, 10);
                 if random < 3 {
-                    let pos_x = rand::gen_range(x, x + cell_size);
-                    let pos_y = rand::gen_range(y, y + cell_size);
+                    let pos_x = rng.range_f32(x, x + cell_size);
+                    let pos_y = rng.range_f32(y, y + cell_size);
                     self.add_random_mover(
                         Vec2::new(pos_x, pos_y),
                         0.0,
-                        rand::gen_range(5.0, 25.0),
-                        rand::gen_range(0.1, 1.5),
+                        rng.range_f32(5.0, 25.0),
+                        rng.range_f32(0.1, 1.5),
                     );
                 }
             }
             200..=255 => {
-                let stones = rand::gen_range(0, 3 + 1);
+                let stones = rng.range_u32(0, 3 + 1);
                 for _ in 0..stones {
-                    let pos_x = rand::gen_range(x, x + cell_size);
-                    let pos_y = rand::gen_range(y, y + cell_size);
+                    let pos_x = rng.range_f32(x, x + cell_size);
+                    let pos_y = rng.range_f32(y, y + cell_size);
                     self.statics.push(Static::Stone(Stone::new(
-                        vec2(pos_x as f32, pos_y as f32),
+                        vec2(pos_x, pos_y),
                         f32::from(noise_value) * 3.0,
-                        rand::gen_range(5.0, max_stone_size) as f32,
+                        rng.range_f32(5.0, max_stone_size),
                     )));
                 }
-                let random = rand::gen_range(0, 10);
+                let random = rng.range_u32(0, 10);
                 if random < 3 {
-                    let pos_x = rand::gen_range(x, x + cell_size);
-                    let pos_y = rand::gen_range(y, y + cell_size);
+                    let pos_x = rng.range_f32(x, x + cell_size);
+                    let pos_y = rng.range_f32(y, y + cell_size);
                     self.add_follower(Vec2::new(pos_x, pos_y));
                 }
             }
         }
     }
 
-    pub fn get_point(&self, x: u32, y: u32) -> f32 {
-        map(
-            self.noise_image
-                .get()
-                .expect("Chunk must be initialised to get the point from the noise image")
-                .get_pixel(x, y)
-                .r,
-            0.0,
-            1.0,
-            0.0,
-            255.0,
-        )
+    /// Seeded from the world seed and the chunk position.
+    fn rng(&self, seed: u64) -> Rng {
+        let ChunkPosition { x, y } = self.position;
+        let x = u64::from(x.cast_unsigned()).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let y = u64::from(y.cast_unsigned()).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        Rng::new(seed ^ x ^ y.rotate_left(32))
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &WorldConfig {
+        &self.config
+    }
+
+    /// World position of the chunk's top left corner.
+    #[must_use]
+    pub fn origin(&self) -> Vec2 {
+        let (x, y) = self.position.offsets(self.config.chunk_world_size());
+        vec2(x, y)
+    }
+
+    /// Noise value at the top left corner of a cell, in the range 0-255. `x` and `y`
+    /// go up to `chunk_size` inclusive, the far edges of the chunk.
+    pub fn get_point(&self, x: u16, y: u16) -> f32 {
+        self.heightfield
+            .get()
+            .expect("Chunk must be initialised to get the point from the heightfield")
+            .get(x, y)
+    }
+
+    #[must_use]
+    pub fn statics(&self) -> &[Static] {
+        &self.statics
+    }
+
+    /// The static at `index`, its cached mesh is rebuilt in case it gets changed.
+    pub fn static_mut(&mut self, index: usize) -> Option<&mut Static> {
+        self.statics_batch.take();
+        self.statics.get_mut(index)
     }
 
     pub fn add_stone(&mut self, position: Vec2, rotation: f32, size: f32) {
         self.statics
             .push(Static::Stone(Stone::new(position, rotation, size)));
+        self.statics_batch.take();
     }
 
     pub fn add_road_segment(&mut self, position: Vec2, rotation: f32, size: f32) {
         self.statics
             .push(Static::Road(Segment::new(position, rotation, size)));
+        self.statics_batch.take();
     }
 
     pub fn add_random_mover(&mut self, position: Vec2, rotation: f32, size: f32, speed: f32) {
-        self.dynamics.push(Some(Box::new(RandomMover::new(
-            position, rotation, size, speed,
-        ))));
+        self.dynamics
+            .push(random_mover(position, rotation, size, speed));
     }
 
     pub fn add_follower(&mut self, position: Vec2) {
-        self.dynamics.push(Some(Box::new(Follower::new(position))));
+        self.dynamics.push(follower(position));
+    }
+
+    /// Runs the systems of every behaviour, then moves the entities. `neighbours` are the
+    /// entity positions of the surrounding chunks, they are only read so chunks can be
+    /// updated at the same time.
+    pub fn update(&mut self, neighbours: &[&[Vec2]]) {
+        self.advance(neighbours, 1);
+    }
+
+    /// Simulates `ticks` ticks in one coarse step, the systems run once and entities keep
+    /// their velocity for the whole step.
+    pub fn advance(&mut self, neighbours: &[&[Vec2]], ticks: u16) {
+        let columns = self.dynamics.columns_mut();
+        wander(
+            &mut self.rng,
+            columns.behaviours,
+            columns.shapes,
+            columns.velocities,
+            self.weather.speed_multiplier(),
+            ticks,
+        );
+        follow(
+            columns.positions,
+            neighbours,
+            columns.behaviours,
+            columns.velocities,
+            ticks,
+        );
+        self.dynamics.apply_velocities(ticks);
     }
 
-    pub fn update(&mut self) {
-        for item in 0..self.dynamics.len() {
-            let mut dynamic = std::mem::replace(&mut self.dynamics[item], None);
-            dynamic
-                .as_mut()
-                .expect("should get dynamic entity after mem::replace")
-                .update(self);
-            self.dynamics[item] = dynamic;
+    /// Simulates the ticks since `simulated_until` up to `tick`, in steps of at most
+    /// `max_step` ticks. A chunk that was frozen out of range catches up with at most
+    /// `MAX_CATCH_UP_STEPS` steps, any time before that is skipped.
+    pub fn simulate_until(&mut self, neighbours: &[&[Vec2]], tick: u64, max_step: u16) {
+        let max_step = max_step.max(1);
+        let mut elapsed = tick
+            .saturating_sub(self.simulated_until)
+            .min(u64::from(max_step) * MAX_CATCH_UP_STEPS);
+        while elapsed > 0 {
+            let step = u16::try_from(elapsed).map_or(max_step, |elapsed| elapsed.min(max_step));
+            self.advance(neighbours, step);
+            elapsed -= u64::from(step);
         }
+        self.simulated_until = self.simulated_until.max(tick);
+    }
+
+    /// Takes out the dynamic entities that moved out of the chunk.
+    pub fn extract_outside_entities(&mut self) -> Dynamics {
+        let area = self.area();
+        self.dynamics.extract(|position| !area.contains(position))
+    }
+
+    /// The part of the world the chunk covers.
+    fn area(&self) -> Rect {
+        let chunk_size = self.config.chunk_world_size();
+        let (x, y) = self.position.offsets(chunk_size);
+        Rect::new(x, y, chunk_size, chunk_size)
     }
 
-    pub fn extract_outside_entities(&mut self) -> Vec<Option<Box<dyn Update>>> {
-        let mut extracted_entities: Vec<Option<Box<dyn Update>>> = Vec::new();
-        let mut i = 0;
-        while i < self.dynamics.len() {
-            if self.in_chunk(
-                (&self.dynamics[i])
-                    .as_ref()
-                    .expect("Should not be mem::replaced in this function.")
-                    .get_pos(),
-            ) {
-                i += 1;
-            } else {
-                let val = self.dynamics.remove(i);
-                extracted_entities.push(val);
+    fn statics_batch(&self) -> &MeshBatch {
+        self.statics_batch.get_or_init(|| {
+            let mut batch = MeshBatch::new();
+            for static_entity in &self.statics {
+                match *static_entity {
+                    Static::Stone(ref stone) => stone.tessellate(&mut batch),
+                    Static::Road(ref segment) => segment.tessellate(&mut batch),
+                    // Drawn as part of the terrain mesh.
+                    Static::Terrain(_) => (),
+                }
             }
-        }
+            batch
+        })
+    }
 
-        extracted_entities
+    /// Axis-aligned box around everything the chunk draws, entities can stick out of
+    /// the chunk's own area.
+    pub fn bounds(&self) -> Rect {
+        let chunk_size = self.config.chunk_world_size();
+        let origin = self.origin();
+        let mut bounds = Rect::new(origin.x, origin.y, chunk_size, chunk_size);
+        if let Some(statics) = self.statics_batch().bounds() {
+            bounds = bounds.combine_with(statics);
+        }
+        self.dynamics
+            .bounds()
+            .map_or(bounds, |dynamics| bounds.combine_with(dynamics))
     }
 
-    fn in_chunk(&self, position: Vec2) -> bool {
-        let chunk_size = f32::from(CHUNK_SIZE) * CHUNK_TILE_SIZE;
-        let (x, y) = self.chunk_position.offsets(chunk_size);
-        let chunk_rect = Rect::new(x, y, chunk_size, chunk_size);
-        chunk_rect.contains(position)
+    /// Draws the parts of the chunk that `view` can see, the caller is expected to have
+    /// checked the chunk's `bounds` already.
+    pub fn draw(&self, view: &ViewFrustum) {
+        for mesh in &self.terrain_meshes {
+            draw_mesh(mesh);
+        }
+        let statics = self.statics_batch();
+        if statics
+            .bounds()
+            .is_some_and(|bounds| view.intersects(bounds))
+        {
+            statics.draw();
+        }
+        for dynamic in self.dynamics.iter() {
+            if view.intersects(dynamic.bounds()) {
+                dynamic.draw();
+            }
+        }
     }
 
-    pub fn draw(&self, viewport: Rect) {
-        let chunk_size = f32::from(CHUNK_SIZE) * CHUNK_TILE_SIZE;
-        let (x, y) = self.chunk_position.offsets(chunk_size);
-        draw_rectangle(x, y, chunk_size, chunk_size, color_u8!(255, 255, 255, 255));
+    /// Draws the statics onto an image, without needing a window.
+    pub fn rasterize(&self, canvas: &mut Canvas) {
         for static_entity in &self.statics {
-            match static_entity {
-                Static::Stone(stone) => stone.draw(viewport),
-                Static::Road(segment) => segment.draw(viewport),
-                Static::Terrain(terrain) => terrain.draw(viewport),
+            match *static_entity {
+                Static::Stone(ref stone) => stone.rasterize(canvas),
+                Static::Road(ref segment) => segment.rasterize(canvas),
+                Static::Terrain(ref terrain) => terrain.rasterize(canvas),
             }
         }
-        for dynamic_entity in &self.dynamics {
-            dynamic_entity
-                .as_ref()
-                .expect("every dynamic entity should be present in draw call")
-                .draw(viewport);
-        }
     }
 
-    pub fn draw_noise_texture(&self, x: f32, y: f32) {
-        let texture = self.noise_texture.get_or_init(|| {
+    /// Draws the part of the heightfield that falls inside `visible`, with the whole
+    /// chunk stretched over `dest`, one texel per cell.
+    pub fn draw_height_texture(&self, visible: Rect, dest: Rect) {
+        let texture = self.height_texture.get_or_init(|| {
             Texture2D::from_image(
-                self.noise_image
+                &self
+                    .heightfield
                     .get()
-                    .expect("noise_image should be initialized before drawing the noise texture"),
+                    .expect("heightfield should be initialized before drawing it")
+                    .to_image(),
             )
         });
-        draw_texture(*texture, x, y, color_u8!(255, 255, 255, 255));
+        // The last row and column belong to the neighbours.
+        draw_texture_part(*texture, f32::from(self.config.chunk_size), visible, dest);
+    }
+
+    /// Like `draw_height_texture`, but samples the noise at full resolution the first
+    /// time it is drawn. Meant for debugging the generator.
+    pub fn draw_noise_texture(&self, noise: &Noise, visible: Rect, dest: Rect) {
+        let texture = self.noise_texture.get_or_init(|| {
+            let _zone = zones::zone(zones::NOISE_CREATION);
+            let config = &self.config;
+            let spacing = config.chunk_world_size() / f32::from(config.noise_image_size);
+            let heightfield = Heightfield::sample(
+                noise,
+                config,
+                config.noise_image_size,
+                self.origin(),
+                spacing,
+            );
+            Texture2D::from_image(&heightfield.to_image())
+        });
+        let size = f32::from(self.config.noise_image_size);
+        draw_texture_part(*texture, size, visible, dest);
     }
 }
 
-/*
-impl Default for Chunk {
-    fn default() -> Self {
-        let top_left = (vec2(0.0, 0.0), "0, 0".to_owned());
-        let btm_right = (
-            vec2(CHUNK_SIZE, CHUNK_SIZE),
-            format!("{}, {}", CHUNK_SIZE, CHUNK_SIZE),
-        );
+/// Draws the part of a square chunk texture that falls inside `visible`, with the whole
+/// texture stretched over `dest`. The noise is tinted towards the terrain colours.
+fn draw_texture_part(texture: Texture2D, size: f32, visible: Rect, dest: Rect) {
+    let source = Rect::new(
+        (visible.x - dest.x) / dest.w * size,
+        (visible.y - dest.y) / dest.h * size,
+        visible.w / dest.w * size,
+        visible.h / dest.h * size,
+    );
+    draw_texture_ex(
+        texture,
+        visible.x,
+        visible.y,
+        color_u8!(60, 255, 180, 255),
+        DrawTextureParams {
+            dest_size: Some(vec2(visible.w, visible.h)),
+            source: Some(source),
+            ..DrawTextureParams::default()
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::inspect::Inspect;
+    use crate::special::batch::MAX_MESH_INDICES;
+
+    fn initialised(x: i32, y: i32, noise: &Noise, config: WorldConfig) -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(x, y), config);
+        chunk.init(noise);
+        chunk
+    }
+
+    #[test]
+    fn large_chunks_draw_their_terrain_in_several_meshes() {
+        let mut noise = Noise::new();
+        noise.set_noise(7, 0.005);
+        let config = WorldConfig {
+            chunk_size: 64,
+            ..WorldConfig::default()
+        };
+        let chunk = initialised(0, 0, &noise, config);
+        let indices: usize = chunk
+            .terrain_meshes
+            .iter()
+            .map(|mesh| mesh.indices.len())
+            .sum();
+        assert_eq!(indices, 64 * 64 * 6);
+        for mesh in &chunk.terrain_meshes {
+            assert!(mesh.indices.len() < MAX_MESH_INDICES);
+        }
+    }
+
+    #[test]
+    fn heightfields_match_across_chunk_edges() {
+        let small = WorldConfig {
+            chunk_size: 8,
+            chunk_tile_size: 300.0,
+            noise_image_size: 64,
+            ..WorldConfig::default()
+        };
+        for config in [WorldConfig::default(), small] {
+            assert_edges_match(config);
+        }
+    }
+
+    /// Both sides sample the noise at the same world position, so they have to be equal.
+    #[allow(clippy::float_cmp)]
+    fn assert_edges_match(config: WorldConfig) {
+        let mut noise = Noise::new();
+        noise.set_noise(1234, 0.005);
+        let range = -2..=2;
+        let chunks: Vec<Chunk> = range
+            .clone()
+            .flat_map(|y| range.clone().map(move |x| (x, y)))
+            .map(|(x, y)| initialised(x, y, &noise, config))
+            .collect();
+        let size = config.chunk_size;
+        let chunk_at = |x: i32, y: i32| {
+            chunks
+                .iter()
+                .find(|chunk| chunk.position == ChunkPosition::new(x, y))
+        };
+
+        for chunk in &chunks {
+            let ChunkPosition { x, y } = chunk.position;
+            if let Some(right) = chunk_at(x + 1, y) {
+                for i in 0..=size {
+                    assert_eq!(
+                        chunk.get_point(size, i),
+                        right.get_point(0, i),
+                        "vertical edge between {} and {}, row {}",
+                        chunk.position,
+                        right.position,
+                        i
+                    );
+                }
+            }
+            if let Some(below) = chunk_at(x, y + 1) {
+                for i in 0..=size {
+                    assert_eq!(
+                        chunk.get_point(i, size),
+                        below.get_point(i, 0),
+                        "horizontal edge between {} and {}, column {}",
+                        chunk.position,
+                        below.position,
+                        i
+                    );
+                }
+            }
+        }
+    }
 
-        let center = (
-            vec2(CHUNK_SIZE / 2.0, CHUNK_SIZE / 2.0),
-            format!("{}, {}", CHUNK_SIZE / 2.0, CHUNK_SIZE / 2.0),
+    /// Regenerate with `UPDATE_SNAPSHOTS=1 cargo test` after intended generation changes.
+    const SNAPSHOT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/special/snapshots/chunk_generation.txt"
+    );
+
+    fn snapshot_config() -> WorldConfig {
+        WorldConfig {
+            chunk_size: 8,
+            noise_image_size: 128,
+            ..WorldConfig::default()
+        }
+    }
+
+    fn populated(position: ChunkPosition, noise: &Noise) -> Chunk {
+        let mut chunk = Chunk::new(position, snapshot_config());
+        chunk.populate(noise);
+        chunk
+    }
+
+    /// Every entity but the terrain, with its kind and position.
+    fn describe(chunk: &Chunk) -> String {
+        let mut lines = vec![format!(
+            "chunk {}: {} statics, {} dynamics",
+            chunk.position,
+            chunk.statics.len(),
+            chunk.dynamics.len()
+        )];
+        let statics = chunk
+            .statics
+            .iter()
+            .filter(|entity| !matches!(**entity, Static::Terrain(_)))
+            .map(|entity| (entity.inspect().kind(), entity.position()));
+        let dynamics = chunk
+            .dynamics
+            .iter()
+            .map(|entity| (entity.kind(), entity.position));
+        for (kind, position) in statics.chain(dynamics) {
+            lines.push(format!("  {kind} {:.1}, {:.1}", position.x, position.y));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn generation_matches_the_snapshot() {
+        let mut report = Vec::new();
+        for seed in [1, 0xdead_beef] {
+            let mut noise = Noise::new();
+            noise.set_noise(seed, snapshot_config().noise_frequency);
+            report.push(format!("seed {seed}"));
+            for (x, y) in [(0, 0), (-1, -2), (7, -3)] {
+                report.push(describe(&populated(ChunkPosition::new(x, y), &noise)));
+            }
+        }
+        let mut report = report.join("\n");
+        report.push('\n');
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(SNAPSHOT, &report).expect("snapshot should be writable");
+            return;
+        }
+        let expected = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            report == expected,
+            "chunk generation changed, compare with {SNAPSHOT} and rerun with UPDATE_SNAPSHOTS=1 \
+             if that was intended:\n{report}"
         );
+    }
+
+    #[test]
+    fn chunks_do_not_depend_on_generation_order() {
+        let mut noise = Noise::new();
+        noise.set_noise(5, snapshot_config().noise_frequency);
+        let positions = [
+            ChunkPosition::new(3, 3),
+            ChunkPosition::new(-4, 2),
+            ChunkPosition::new(0, -9),
+        ];
+        let forwards: Vec<String> = positions
+            .iter()
+            .map(|&position| describe(&populated(position, &noise)))
+            .collect();
+        let mut backwards: Vec<String> = positions
+            .iter()
+            .rev()
+            .map(|&position| describe(&populated(position, &noise)))
+            .collect();
+        backwards.reverse();
+        assert_eq!(forwards, backwards);
 
-        let top_right = (vec2(CHUNK_SIZE, 0.0), format!("{}, {}", CHUNK_SIZE, 0.0));
+        let mut other = Noise::new();
+        other.set_noise(6, snapshot_config().noise_frequency);
+        let (first, &first_position) = forwards
+            .first()
+            .zip(positions.first())
+            .expect("there should be positions to generate");
+        assert_ne!(*first, describe(&populated(first_position, &other)));
+    }
+
+    #[test]
+    fn heightfield_follows_the_world_noise() {
+        let mut noise = Noise::new();
+        noise.set_noise(99, 0.005);
+        let config = WorldConfig::default();
+        let chunk = initialised(-3, 2, &noise, config);
+        let size = config.chunk_size;
+        for (x, y) in [(0, 0), (5, 11), (size, size)] {
+            let world = chunk.origin() + vec2(f32::from(x), f32::from(y)) * config.chunk_tile_size;
+            let expected = noise.sample_world(world, &config);
+            assert!((chunk.get_point(x, y) - expected).abs() < f32::EPSILON);
+        }
+    }
 
-        let btm_left = (vec2(0.0, CHUNK_SIZE), format!("{}, {}", 0.0, CHUNK_SIZE));
+    #[test]
+    fn frozen_chunks_catch_up_a_bounded_amount() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), snapshot_config());
+        let start = vec2(100.0, 100.0);
+        chunk.add_random_mover(start, 0.0, 10.0, 1.0);
+        chunk.simulate_until(&[], 10_000, 8);
+        assert_eq!(chunk.simulated_until, 10_000);
+        let moved = chunk
+            .dynamics
+            .positions()
+            .first()
+            .expect("the mover should still be in the chunk")
+            .distance(start);
+        assert!(moved > 0.0);
+        // A step covers at most 8 ticks at 1 unit per tick.
+        assert!(moved <= 8.0_f32.mul_add(32.0, 1e-3));
 
-        let stuff = vec![top_left, btm_right, center, top_right, btm_left];
-        Self { stuff }
+        // Already up to date, nothing left to simulate.
+        let before = chunk.dynamics.clone();
+        chunk.simulate_until(&[], 10_000, 8);
+        assert_eq!(chunk.dynamics, before);
     }
 }
-*/
@@ -0,0 +1,268 @@
+use std::fmt::Display;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::special::parallel;
+
+/// Chunks are generated whole on the frame they're first needed, larger ones would stall it.
+/// Their terrain is meshed in tiles, so the size isn't bound by what a draw call holds.
+const MAX_CHUNK_SIZE: u16 = 254;
+
+/// The parameters the world is built with, read from a ron file and the command line.
+/// Missing fields keep their defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    /// Cells along each side of a chunk.
+    pub chunk_size: u16,
+    /// World units along each side of a cell.
+    pub chunk_tile_size: f32,
+    /// Noise samples along each side of a chunk, sets the scale of the terrain.
+    pub noise_image_size: u16,
+    /// What new worlds start with, the creation screen and saves can change it.
+    pub noise_frequency: f32,
+    /// Chunks around the player that are drawn.
+    pub render_distance: i32,
+    /// Chunks around the player that are simulated.
+    pub update_distance: i32,
+    /// Chunks around the player that are simulated every tick, the ones further away only
+    /// every `far_update_interval` ticks, in one coarse step.
+    pub full_rate_distance: i32,
+    /// Ticks between the updates of far chunks, also the longest step frozen chunks catch
+    /// up with. 1 simulates every chunk at full rate.
+    pub far_update_interval: u16,
+    /// Threads chunks are updated on, 0 uses one per core. The simulation gives the same
+    /// result with any number of them.
+    pub update_threads: u16,
+}
+
+impl WorldConfig {
+    /// Loads `path` if given, then applies the `name=value` overrides in order.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read, a value is wrong or the result isn't valid.
+    pub fn resolve(
+        path: Option<&str>,
+        overrides: &[(String, String)],
+    ) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => Self::read(path)?,
+            None => Self::default(),
+        };
+        for name_value in overrides {
+            config.set(&name_value.0, &name_value.1)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// # Errors
+    ///
+    /// Fails if `path` can't be read or doesn't contain a config.
+    pub fn read(path: &str) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    /// Sets a field by name, without validating the result.
+    ///
+    /// # Errors
+    ///
+    /// Fails on unknown names and values that don't parse.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), ConfigError> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+            value
+                .parse()
+                .map_err(|_| ConfigError::Value(name.to_owned(), value.to_owned()))
+        }
+        match name {
+            "chunk_size" => self.chunk_size = parse(name, value)?,
+            "chunk_tile_size" => self.chunk_tile_size = parse(name, value)?,
+            "noise_image_size" => self.noise_image_size = parse(name, value)?,
+            "noise_frequency" => self.noise_frequency = parse(name, value)?,
+            "render_distance" => self.render_distance = parse(name, value)?,
+            "update_distance" => self.update_distance = parse(name, value)?,
+            "update_threads" => self.update_threads = parse(name, value)?,
+            "full_rate_distance" => self.full_rate_distance = parse(name, value)?,
+            "far_update_interval" => self.far_update_interval = parse(name, value)?,
+            _ => return Err(ConfigError::Unknown(name.to_owned())),
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Describes the first problem found.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason: &str| Err(ConfigError::Invalid(reason.to_owned()));
+        if self.chunk_size == 0 || self.chunk_size > MAX_CHUNK_SIZE {
+            return invalid(&format!(
+                "chunk_size must be between 1 and {MAX_CHUNK_SIZE}"
+            ));
+        }
+        if !(self.chunk_tile_size.is_finite() && self.chunk_tile_size > 0.0) {
+            return invalid("chunk_tile_size must be positive");
+        }
+        if self.noise_image_size == 0 || !self.noise_image_size.is_multiple_of(self.chunk_size) {
+            return invalid("noise_image_size must be a multiple of chunk_size");
+        }
+        if !(self.noise_frequency.is_finite() && self.noise_frequency > 0.0) {
+            return invalid("noise_frequency must be positive");
+        }
+        if self.render_distance < 0 || self.update_distance < 0 || self.full_rate_distance < 0 {
+            return invalid(
+                "render_distance, update_distance and full_rate_distance can't be negative",
+            );
+        }
+        if self.full_rate_distance > self.update_distance {
+            return invalid("full_rate_distance can't be larger than update_distance");
+        }
+        if self.far_update_interval == 0 {
+            return invalid("far_update_interval must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// World units along each side of a chunk.
+    #[must_use]
+    pub fn chunk_world_size(&self) -> f32 {
+        f32::from(self.chunk_size) * self.chunk_tile_size
+    }
+
+    /// How many threads chunk updates are spread over.
+    #[must_use]
+    pub fn update_threads(&self) -> usize {
+        match self.update_threads {
+            0 => parallel::default_threads(),
+            threads => usize::from(threads),
+        }
+    }
+
+    /// Noise space units per world unit.
+    #[must_use]
+    pub fn noise_scale(&self) -> f32 {
+        f32::from(self.noise_image_size) / self.chunk_world_size()
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16,
+            chunk_tile_size: 400.0,
+            noise_image_size: 256,
+            noise_frequency: 0.005,
+            render_distance: 2,
+            update_distance: 5,
+            full_rate_distance: 2,
+            far_update_interval: 8,
+            update_threads: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Unknown(String),
+    Value(String, String),
+    Invalid(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Io(ref error) => write!(f, "config file io error: {error}"),
+            Self::Parse(ref error) => write!(f, "could not parse the config: {error}"),
+            Self::Unknown(ref name) => write!(f, "unknown config value `{name}`"),
+            Self::Value(ref name, ref value) => {
+                write!(f, "`{value}` is not a valid value for `{name}`")
+            }
+            Self::Invalid(ref reason) => write!(f, "invalid config: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for ConfigError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(WorldConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn partial_files_keep_the_defaults() {
+        let config: WorldConfig = ron::from_str("(chunk_size: 8, render_distance: 3)")
+            .expect("partial config should parse");
+        assert_eq!(config.chunk_size, 8);
+        assert_eq!(config.render_distance, 3);
+        assert_eq!(
+            config.noise_image_size,
+            WorldConfig::default().noise_image_size
+        );
+    }
+
+    #[test]
+    fn overrides_are_applied_and_validated() {
+        let overrides = [("chunk_size".to_owned(), "32".to_owned())];
+        let config = WorldConfig::resolve(None, &overrides).expect("32 divides 256");
+        assert_eq!(config.chunk_size, 32);
+        let overrides = [
+            ("chunk_size".to_owned(), "254".to_owned()),
+            ("noise_image_size".to_owned(), "508".to_owned()),
+        ];
+        let config = WorldConfig::resolve(None, &overrides).expect("254 is the largest size");
+        assert_eq!(config.chunk_size, 254);
+        let overrides = [
+            ("chunk_size".to_owned(), "255".to_owned()),
+            ("noise_image_size".to_owned(), "510".to_owned()),
+        ];
+        assert!(matches!(
+            WorldConfig::resolve(None, &overrides),
+            Err(ConfigError::Invalid(_))
+        ));
+
+        let overrides = [("chunk_size".to_owned(), "15".to_owned())];
+        assert!(matches!(
+            WorldConfig::resolve(None, &overrides),
+            Err(ConfigError::Invalid(_))
+        ));
+        let overrides = [("chunk_size".to_owned(), "big".to_owned())];
+        assert!(matches!(
+            WorldConfig::resolve(None, &overrides),
+            Err(ConfigError::Value(_, _))
+        ));
+        let overrides = [("speed".to_owned(), "1".to_owned())];
+        assert!(matches!(
+            WorldConfig::resolve(None, &overrides),
+            Err(ConfigError::Unknown(_))
+        ));
+        let overrides = [
+            ("update_distance".to_owned(), "2".to_owned()),
+            ("full_rate_distance".to_owned(), "3".to_owned()),
+        ];
+        assert!(matches!(
+            WorldConfig::resolve(None, &overrides),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use macroquad::input::{get_char_pressed, is_key_pressed, KeyCode};
+use macroquad::math::vec2;
+use macroquad::ui::{hash, root_ui, widgets::Window};
+use macroquad::window::screen_width;
+
+/// How many lines of output the console keeps.
+const OUTPUT_LINES: usize = 200;
+/// How many of them fit in the window.
+const VISIBLE_LINES: usize = 14;
+
+pub type CommandFn<C> = fn(&mut C, &Args) -> Result<String, CommandError>;
+
+/// A command that can be typed into the console, `C` is whatever it acts on.
+pub struct Command<C> {
+    /// One or more words, e.g. `chunk info`.
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    run: CommandFn<C>,
+}
+
+// Derived impls would require `C: Clone`.
+impl<C> Clone for Command<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Command<C> {}
+
+impl<C> Command<C> {
+    /// # Errors
+    ///
+    /// Whatever the command fails with.
+    pub fn run(&self, context: &mut C, args: &Args) -> Result<String, CommandError> {
+        (self.run)(context, args)
+    }
+}
+
+/// The words typed after a command's name.
+pub struct Args<'a> {
+    words: Vec<&'a str>,
+    usage: &'static str,
+}
+
+impl<'a> Args<'a> {
+    #[must_use]
+    pub const fn new(words: Vec<&'a str>, usage: &'static str) -> Self {
+        Self { words, usage }
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// For arguments that parse but make no sense to the command.
+    #[must_use]
+    pub const fn usage_error(&self) -> CommandError {
+        CommandError::Usage(self.usage)
+    }
+
+    /// # Errors
+    ///
+    /// Fails with the command's usage when the word is missing.
+    pub fn word(&self, index: usize) -> Result<&'a str, CommandError> {
+        self.words
+            .get(index)
+            .copied()
+            .ok_or_else(|| self.usage_error())
+    }
+
+    /// # Errors
+    ///
+    /// Fails with the command's usage when the word is missing or doesn't parse.
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, CommandError> {
+        self.word(index)?.parse().map_err(|_| self.usage_error())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CommandError {
+    Unknown(String),
+    /// The arguments don't fit the command, holds its usage.
+    Usage(&'static str),
+    Failed(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Unknown(ref name) => write!(f, "unknown command `{name}`, try `help`"),
+            Self::Usage(usage) => write!(f, "usage: {usage}"),
+            Self::Failed(ref reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// All commands the console knows, anything can register more.
+pub struct CommandRegistry<C> {
+    commands: Vec<Command<C>>,
+}
+
+impl<C> CommandRegistry<C> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Replaces any command with the same name.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        run: CommandFn<C>,
+    ) -> &mut Self {
+        self.commands.retain(|command| command.name != name);
+        self.commands.push(Command {
+            name,
+            usage,
+            help,
+            run,
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn commands(&self) -> &[Command<C>] {
+        &self.commands
+    }
+
+    /// Splits `line` into the command it starts with and its arguments. The command
+    /// with the most matching words wins, so `chunk info` and `chunk` can both exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no command matches.
+    pub fn find<'a>(&self, line: &'a str) -> Result<(Command<C>, Args<'a>), CommandError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command = self
+            .commands
+            .iter()
+            .filter(|command| {
+                let name = command.name.split_whitespace();
+                name.clone().count() <= words.len() && name.zip(&words).all(|(a, &b)| a == b)
+            })
+            .max_by_key(|command| command.name.split_whitespace().count())
+            .ok_or_else(|| {
+                CommandError::Unknown(words.first().copied().unwrap_or("").to_owned())
+            })?;
+        let args = words
+            .get(command.name.split_whitespace().count()..)
+            .unwrap_or_default()
+            .to_vec();
+        Ok((*command, Args::new(args, command.usage)))
+    }
+
+    /// One line per command.
+    #[must_use]
+    pub fn help(&self) -> String {
+        self.commands
+            .iter()
+            .map(|command| format!("{:<28} {}", command.usage, command.help))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<C> Default for CommandRegistry<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Toggled with the backtick key. Lines typed into it are handed back to the owner, which
+/// runs them against its `CommandRegistry`.
+pub struct Console<C> {
+    open: bool,
+    input: String,
+    output: Vec<String>,
+    history: Vec<String>,
+    /// Index into `history` while stepping through it with the arrow keys.
+    history_cursor: Option<usize>,
+    commands: CommandRegistry<C>,
+}
+
+impl<C> Console<C> {
+    #[must_use]
+    pub const fn new(commands: CommandRegistry<C>) -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+            history: Vec::new(),
+            history_cursor: None,
+            commands,
+        }
+    }
+
+    /// While open the console takes all keyboard input.
+    #[must_use]
+    pub const fn is_open(&self) -> bool {
+        self.open
+    }
+
+    #[must_use]
+    pub const fn commands(&self) -> &CommandRegistry<C> {
+        &self.commands
+    }
+
+    pub const fn commands_mut(&mut self) -> &mut CommandRegistry<C> {
+        &mut self.commands
+    }
+
+    #[must_use]
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn print(&mut self, text: &str) {
+        self.output.extend(text.lines().map(str::to_owned));
+        let overflow = self.output.len().saturating_sub(OUTPUT_LINES);
+        self.output.drain(..overflow);
+    }
+
+    pub fn clear(&mut self) {
+        self.output.clear();
+    }
+
+    /// Handles typing, returns a line once enter is pressed.
+    pub fn input(&mut self) -> Option<String> {
+        if is_key_pressed(KeyCode::GraveAccent) {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return None;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.open = false;
+        }
+        while let Some(character) = get_char_pressed() {
+            if character != '`' && !character.is_control() {
+                self.input.push(character);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.history_previous();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.history_next();
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            return self.submit();
+        }
+        None
+    }
+
+    /// Takes the typed line, echoing it and remembering it in the history.
+    fn submit(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.input);
+        self.history_cursor = None;
+        if line.trim().is_empty() {
+            return None;
+        }
+        self.print(&format!("> {line}"));
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        Some(line)
+    }
+
+    fn history_previous(&mut self) {
+        let index = match self.history_cursor {
+            Some(index) => index.saturating_sub(1),
+            None => match self.history.len().checked_sub(1) {
+                Some(index) => index,
+                None => return,
+            },
+        };
+        self.show_history(Some(index));
+    }
+
+    fn history_next(&mut self) {
+        let index = self
+            .history_cursor
+            .map(|index| index + 1)
+            .filter(|&index| index < self.history.len());
+        self.show_history(index);
+    }
+
+    fn show_history(&mut self, index: Option<usize>) {
+        self.history_cursor = index;
+        self.input = index
+            .and_then(|index| self.history.get(index))
+            .cloned()
+            .unwrap_or_default();
+    }
+
+    pub fn ui(&self) {
+        if !self.open {
+            return;
+        }
+        let size = vec2(screen_width().min(700.0) - 20.0, 300.0);
+        Window::new(hash!(), vec2(10.0, 80.0), size)
+            .label("Console")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                let start = self.output.len().saturating_sub(VISIBLE_LINES);
+                for line in self.output.get(start..).unwrap_or_default() {
+                    ui.label(None, line);
+                }
+                ui.separator();
+                ui.label(None, &format!("> {}_", self.input));
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> CommandRegistry<Vec<String>> {
+        let mut registry: CommandRegistry<Vec<String>> = CommandRegistry::new();
+        registry
+            .register("chunk", "chunk", "", |log, _| {
+                log.push("chunk".to_owned());
+                Ok(String::new())
+            })
+            .register("chunk info", "chunk info", "", |log, _| {
+                log.push("chunk info".to_owned());
+                Ok(String::new())
+            })
+            .register("tp", "tp <x> <y>", "", |log, args| {
+                let (x, y): (f32, f32) = (args.parse(0)?, args.parse(1)?);
+                log.push(format!("tp {x} {y}"));
+                Ok(String::new())
+            });
+        registry
+    }
+
+    fn run(
+        registry: &CommandRegistry<Vec<String>>,
+        line: &str,
+    ) -> Result<Vec<String>, CommandError> {
+        let mut log = Vec::new();
+        let (command, args) = registry.find(line)?;
+        command.run(&mut log, &args)?;
+        Ok(log)
+    }
+
+    #[test]
+    fn the_longest_matching_name_wins() {
+        let registry = registry();
+        assert_eq!(
+            run(&registry, "chunk info"),
+            Ok(vec!["chunk info".to_owned()])
+        );
+        assert_eq!(run(&registry, "chunk"), Ok(vec!["chunk".to_owned()]));
+        assert_eq!(run(&registry, "chunk other"), Ok(vec!["chunk".to_owned()]));
+    }
+
+    #[test]
+    fn arguments_are_parsed_or_show_the_usage() {
+        let registry = registry();
+        assert_eq!(
+            run(&registry, "  tp 1  -2.5 "),
+            Ok(vec!["tp 1 -2.5".to_owned()])
+        );
+        assert_eq!(
+            run(&registry, "tp 1"),
+            Err(CommandError::Usage("tp <x> <y>"))
+        );
+        assert_eq!(
+            run(&registry, "tp a b"),
+            Err(CommandError::Usage("tp <x> <y>"))
+        );
+        assert_eq!(
+            run(&registry, "fly 1"),
+            Err(CommandError::Unknown("fly".to_owned()))
+        );
+    }
+
+    #[test]
+    fn history_steps_through_submitted_lines() {
+        let mut console = Console::new(registry());
+        for line in ["seed 1", "seed 1", "tp 0 0"] {
+            console.input = line.to_owned();
+            assert_eq!(console.submit(), Some(line.to_owned()));
+        }
+        assert_eq!(console.history(), ["seed 1", "tp 0 0"]);
+
+        console.history_previous();
+        assert_eq!(console.input, "tp 0 0");
+        console.history_previous();
+        console.history_previous();
+        assert_eq!(console.input, "seed 1");
+        console.history_next();
+        assert_eq!(console.input, "tp 0 0");
+        console.history_next();
+        assert_eq!(console.input, "");
+    }
+}
@@ -0,0 +1,116 @@
+use macroquad::color::Color;
+use macroquad::color_u8;
+use macroquad::math::vec2;
+use macroquad::rand;
+use macroquad::texture::{FilterMode, Image, Texture2D};
+use macroquad::ui::{hash, root_ui, widgets::Window};
+use macroquad::window::{screen_height, screen_width};
+
+use crate::common::parse_seed;
+use crate::special::config::WorldConfig;
+use crate::special::noise::Noise;
+use crate::special::noise_graph::NoiseNode;
+use crate::world::WorldSettings;
+
+const PREVIEW_PIXELS: u16 = 128;
+/// How many chunks the preview spans in each direction.
+const PREVIEW_CHUNKS: u16 = 4;
+const MIN_FREQUENCY: f32 = 0.001;
+const MAX_FREQUENCY: f32 = 0.02;
+
+/// The world creation screen: pick a seed and the generator parameters, look at a
+/// preview of the terrain and start.
+pub struct WorldCreation {
+    seed_text: String,
+    noise_frequency: f32,
+    noise_graph: NoiseNode,
+    config: WorldConfig,
+
+    preview: Option<Texture2D>,
+    previewed: Option<WorldSettings>,
+}
+
+impl WorldCreation {
+    #[must_use]
+    pub fn new(defaults: WorldSettings, noise_graph: NoiseNode, config: WorldConfig) -> Self {
+        Self {
+            seed_text: defaults.seed.to_string(),
+            noise_frequency: defaults.noise_frequency,
+            noise_graph,
+            config,
+            preview: None,
+            previewed: None,
+        }
+    }
+
+    #[must_use]
+    pub fn settings(&self) -> WorldSettings {
+        WorldSettings {
+            seed: parse_seed(&self.seed_text),
+            noise_frequency: self.noise_frequency,
+        }
+    }
+
+    /// Draws the screen, returns the chosen settings once the world should be started.
+    pub fn ui(&mut self) -> Option<WorldSettings> {
+        self.update_preview();
+
+        let size = vec2(420.0, 400.0);
+        let position = vec2(
+            (screen_width() - size.x) / 2.0,
+            (screen_height() - size.y) / 2.0,
+        );
+        let mut start = false;
+        Window::new(hash!(), position, size)
+            .label("New world")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                ui.input_text(hash!(), "seed", &mut self.seed_text);
+                if ui.button(None, "random") {
+                    self.seed_text = rand::rand().to_string();
+                }
+                ui.slider(
+                    hash!(),
+                    "frequency",
+                    MIN_FREQUENCY..MAX_FREQUENCY,
+                    &mut self.noise_frequency,
+                );
+                ui.label(None, &format!("seed value: {}", self.settings().seed));
+                if let Some(preview) = self.preview {
+                    ui.texture(preview, 256.0, 256.0);
+                }
+                start = ui.button(None, "start");
+            });
+        start.then(|| self.settings())
+    }
+
+    fn update_preview(&mut self) {
+        let settings = self.settings();
+        if self.previewed == Some(settings) {
+            return;
+        }
+        let mut noise = Noise::new();
+        noise.set_graph(&self.noise_graph, settings.seed, settings.noise_frequency);
+        // World units per preview pixel, so the preview spans several chunks.
+        let step =
+            f32::from(PREVIEW_CHUNKS) * self.config.chunk_world_size() / f32::from(PREVIEW_PIXELS);
+        let half = f32::from(PREVIEW_PIXELS) / 2.0;
+        let tint = color_u8!(60, 255, 180, 255);
+        let mut image = Image::gen_image_color(PREVIEW_PIXELS, PREVIEW_PIXELS, tint);
+        for y in 0..PREVIEW_PIXELS {
+            for x in 0..PREVIEW_PIXELS {
+                let world = vec2(f32::from(x) - half, f32::from(y) - half) * step;
+                let value = noise.sample_world(world, &self.config) / 255.0;
+                let color = Color::new(tint.r * value, tint.g * value, tint.b * value, 1.0);
+                image.set_pixel(u32::from(x), u32::from(y), color);
+            }
+        }
+
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        if let Some(old) = self.preview.replace(texture) {
+            old.delete();
+        }
+        self.previewed = Some(settings);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use macroquad::math::{Rect, Vec2};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::floor_i32;
+use crate::special::config::WorldConfig;
+use crate::world::ChunkPosition;
+
+/// Which cells of a single chunk have been seen, one bit per cell.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExploredCells {
+    /// Cells along each side, `WorldConfig::chunk_size` when the chunk was explored.
+    #[serde(default = "default_size")]
+    size: u16,
+    bits: Vec<u64>,
+}
+
+impl ExploredCells {
+    #[must_use]
+    pub fn new(size: u16) -> Self {
+        let cells = usize::from(size) * usize::from(size);
+        Self {
+            size,
+            bits: vec![0; cells.div_ceil(64)],
+        }
+    }
+
+    /// Word and bit of a cell, `None` outside of the chunk.
+    fn index(&self, x: usize, y: usize) -> Option<(usize, u64)> {
+        let size = usize::from(self.size);
+        if x >= size || y >= size {
+            return None;
+        }
+        let cell = y * size + x;
+        Some((cell >> 6, 1 << (cell & 63)))
+    }
+
+    #[must_use]
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        self.index(x, y)
+            .is_some_and(|(word, mask)| self.bits.get(word).is_some_and(|bits| bits & mask != 0))
+    }
+
+    pub fn reveal(&mut self, x: usize, y: usize) {
+        if let Some((word, mask)) = self.index(x, y) {
+            if let Some(bits) = self.bits.get_mut(word) {
+                *bits |= mask;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn count(&self) -> u32 {
+        self.bits.iter().map(|bits| bits.count_ones()).sum()
+    }
+}
+
+fn default_size() -> u16 {
+    WorldConfig::default().chunk_size
+}
+
+/// Exploration state of the whole world, recorded as the player's view sweeps over it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exploration {
+    chunks: HashMap<ChunkPosition, ExploredCells>,
+}
+
+impl Exploration {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Marks every cell overlapping `area` as explored.
+    pub fn reveal(&mut self, area: Rect, config: &WorldConfig) {
+        let (Some((left, top)), Some((right, bottom))) = (
+            cell_of(area.point(), config),
+            cell_of(area.point() + area.size(), config),
+        ) else {
+            return;
+        };
+        let chunk_cells = i32::from(config.chunk_size);
+        for y in top..=bottom {
+            for x in left..=right {
+                let chunk =
+                    ChunkPosition::new(x.div_euclid(chunk_cells), y.div_euclid(chunk_cells));
+                let (local_x, local_y) = (x.rem_euclid(chunk_cells), y.rem_euclid(chunk_cells));
+                self.chunks
+                    .entry(chunk)
+                    .or_insert_with(|| ExploredCells::new(config.chunk_size))
+                    .reveal(
+                        usize::try_from(local_x).unwrap_or_default(),
+                        usize::try_from(local_y).unwrap_or_default(),
+                    );
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn chunk(&self, position: ChunkPosition) -> Option<&ExploredCells> {
+        self.chunks.get(&position)
+    }
+
+    #[must_use]
+    pub fn is_explored(&self, position: Vec2, config: &WorldConfig) -> bool {
+        let Some((x, y)) = cell_of(position, config) else {
+            return false;
+        };
+        let chunk_cells = i32::from(config.chunk_size);
+        let chunk = ChunkPosition::new(x.div_euclid(chunk_cells), y.div_euclid(chunk_cells));
+        self.chunk(chunk).is_some_and(|cells| {
+            cells.is_explored(
+                usize::try_from(x.rem_euclid(chunk_cells)).unwrap_or_default(),
+                usize::try_from(y.rem_euclid(chunk_cells)).unwrap_or_default(),
+            )
+        })
+    }
+}
+
+/// Global cell coordinates of a world position, `None` past the range of the cells.
+fn cell_of(position: Vec2, config: &WorldConfig) -> Option<(i32, i32)> {
+    Some((
+        floor_i32(position.x / config.chunk_tile_size)?,
+        floor_i32(position.y / config.chunk_tile_size)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_at_the_chunk_edges_read_back() {
+        // 81 cells, so the last row straddles two words.
+        for size in [9, 16] {
+            let last = usize::from(size) - 1;
+            let mut cells = ExploredCells::new(size);
+            let edges = [(0, 0), (last, 0), (0, last), (last, last), (7, 7)];
+            for &(x, y) in &edges {
+                cells.reveal(x, y);
+            }
+            for y in 0..=last {
+                for x in 0..=last {
+                    assert_eq!(cells.is_explored(x, y), edges.contains(&(x, y)));
+                }
+            }
+            assert_eq!(cells.count(), 5);
+            // Out of the chunk, nothing is set or read.
+            cells.reveal(last, last + 1);
+            assert!(!cells.is_explored(last, last + 1));
+            assert_eq!(cells.count(), 5);
+        }
+    }
+
+    #[test]
+    fn revealing_across_a_chunk_border_marks_both_sides() {
+        let config = WorldConfig::default();
+        let mut exploration = Exploration::new();
+        let tile = config.chunk_tile_size;
+        // The last cell of chunk (-1, -1) and the first of chunk (0, 0).
+        exploration.reveal(Rect::new(-tile * 0.5, -tile * 0.5, tile, tile), &config);
+        let last = usize::from(config.chunk_size) - 1;
+        let before = exploration
+            .chunk(ChunkPosition::new(-1, -1))
+            .expect("revealed the corner of the chunk");
+        assert!(before.is_explored(last, last));
+        assert_eq!(before.count(), 1);
+        assert!(exploration.is_explored(Vec2::new(tile * 0.5, tile * 0.5), &config));
+        assert!(!exploration.is_explored(Vec2::new(tile * 1.5, 0.0), &config));
+        assert!(!exploration.is_explored(Vec2::new(f32::NAN, 0.0), &config));
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+
+use macroquad::color::Color;
+use macroquad::color_u8;
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::texture::Image;
+
+use image::{ColorType, ImageError, ImageFormat};
+
+use crate::cli::ExportOptions;
+use crate::common::floor_i32;
+use crate::special::chunk::Chunk;
+use crate::special::config::WorldConfig;
+use crate::special::noise::Noise;
+use crate::special::noise_graph::{NoiseGraphError, NoiseNode};
+use crate::world::ChunkPosition;
+
+/// Gap between the panels of different seeds.
+const PANEL_GAP: u16 = 8;
+
+/// An image that world-space shapes can be rasterised onto, the headless counterpart of
+/// drawing with the main camera.
+pub struct Canvas {
+    image: Image,
+    /// Where the canvas draws to, in pixels. Nothing is drawn outside of it.
+    panel: Rect,
+    /// World position of the panel's top left corner.
+    origin: Vec2,
+    pixels_per_unit: f32,
+}
+
+impl Canvas {
+    fn to_pixels(&self, world: Vec2) -> Vec2 {
+        self.panel.point() + (world - self.origin) * self.pixels_per_unit
+    }
+
+    fn to_world(&self, pixel: Vec2) -> Vec2 {
+        self.origin + (pixel - self.panel.point()) / self.pixels_per_unit
+    }
+
+    /// Pixel bounds of a world rect, clamped to the panel.
+    fn pixel_bounds(&self, world: Rect) -> (u16, u16, u16, u16) {
+        let start = self.to_pixels(world.point());
+        let end = self.to_pixels(world.point() + world.size());
+        // The panel is inside the image, so clamped values always fit.
+        let clamp = |value: f32, min: f32, max: f32| {
+            floor_i32(value.clamp(min, max))
+                .and_then(|value| u16::try_from(value).ok())
+                .unwrap_or_default()
+        };
+        (
+            clamp(start.x.floor(), self.panel.left(), self.panel.right()),
+            clamp(start.y.floor(), self.panel.top(), self.panel.bottom()),
+            clamp(end.x.ceil(), self.panel.left(), self.panel.right()),
+            clamp(end.y.ceil(), self.panel.top(), self.panel.bottom()),
+        )
+    }
+
+    /// Alpha blends `color` over the pixel.
+    fn blend(&mut self, x: u32, y: u32, color: Color) {
+        let under = self.image.get_pixel(x, y);
+        let a = color.a;
+        let blended = Color::new(
+            color.r.mul_add(a, under.r * (1.0 - a)),
+            color.g.mul_add(a, under.g * (1.0 - a)),
+            color.b.mul_add(a, under.b * (1.0 - a)),
+            1.0,
+        );
+        self.image.set_pixel(x, y, blended);
+    }
+
+    fn fill_where(&mut self, bounds: Rect, color: Color, inside: impl Fn(Vec2) -> bool) {
+        let (left, top, right, bottom) = self.pixel_bounds(bounds);
+        for y in top..bottom {
+            for x in left..right {
+                let center = self.to_world(vec2(f32::from(x) + 0.5, f32::from(y) + 0.5));
+                if inside(center) {
+                    self.blend(u32::from(x), u32::from(y), color);
+                }
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.fill_where(rect, color, |_| true);
+    }
+
+    pub fn fill_circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let bounds = Rect::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2.0,
+            radius * 2.0,
+        );
+        // Keep tiny shapes visible at low resolutions.
+        let radius = radius.max(0.5 / self.pixels_per_unit);
+        self.fill_where(bounds, color, |point| point.distance(center) <= radius);
+    }
+
+    pub fn draw_line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        let half = (thickness / 2.0).max(0.5 / self.pixels_per_unit);
+        let bounds = Rect::new(
+            start.x.min(end.x) - half,
+            start.y.min(end.y) - half,
+            (start.x - end.x).abs() + half * 2.0,
+            (start.y - end.y).abs() + half * 2.0,
+        );
+        let direction = end - start;
+        let length_squared = direction.length_squared();
+        self.fill_where(bounds, color, |point| {
+            let along = if length_squared > 0.0 {
+                ((point - start).dot(direction) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            point.distance(start + direction * along) <= half
+        });
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    TooLarge { width: u32, height: u32 },
+    Io(std::io::Error),
+    NoiseGraph(NoiseGraphError),
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::TooLarge { width, height } => write!(
+                f,
+                "the image would be {}x{} pixels, at most {}x{} is supported",
+                width,
+                height,
+                u16::MAX,
+                u16::MAX
+            ),
+            Self::Io(ref error) => write!(f, "could not write the image: {error}"),
+            Self::NoiseGraph(ref error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<std::io::Error> for ExportError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ImageError> for ExportError {
+    fn from(error: ImageError) -> Self {
+        match error {
+            ImageError::IoError(error) => Self::Io(error),
+            error => Self::Io(io::Error::other(error)),
+        }
+    }
+}
+
+impl From<NoiseGraphError> for ExportError {
+    fn from(error: NoiseGraphError) -> Self {
+        Self::NoiseGraph(error)
+    }
+}
+
+/// Generates the requested chunks for every seed and writes them to a png, side by side.
+///
+/// # Errors
+///
+/// Fails if the image would be too large or can't be written, or the noise graph can't be loaded.
+pub fn export_region(options: &ExportOptions, config: &WorldConfig) -> Result<(), ExportError> {
+    let (min_x, max_x) = (
+        options.from.x.min(options.to.x),
+        options.from.x.max(options.to.x),
+    );
+    let (min_y, max_y) = (
+        options.from.y.min(options.to.y),
+        options.from.y.max(options.to.y),
+    );
+    let chunk_pixels = u32::from(config.chunk_size) * u32::from(options.cell_pixels);
+    // Sizes that overflow are reported as `u32::MAX`, they are too large either way.
+    let panel_pixels = |min: i32, max: i32| {
+        max.abs_diff(min)
+            .checked_add(1)
+            .and_then(|chunks| chunks.checked_mul(chunk_pixels))
+            .unwrap_or(u32::MAX)
+    };
+    let panel_width = panel_pixels(min_x, max_x);
+    let panel_height = panel_pixels(min_y, max_y);
+    let panels = u32::try_from(options.seeds.len()).unwrap_or(u32::MAX);
+    let width = panel_width
+        .checked_mul(panels)
+        .and_then(|width| {
+            width.checked_add(u32::from(PANEL_GAP).checked_mul(panels.saturating_sub(1))?)
+        })
+        .unwrap_or(u32::MAX);
+    let (Ok(image_width), Ok(image_height), Ok(panel_width)) = (
+        u16::try_from(width),
+        u16::try_from(panel_height),
+        u16::try_from(panel_width),
+    ) else {
+        return Err(ExportError::TooLarge {
+            width,
+            height: panel_height,
+        });
+    };
+    let noise_graph = match options.noise_graph {
+        Some(ref path) => NoiseNode::load(path)?,
+        None => NoiseNode::default(),
+    };
+    // Fail before generating anything if the file can't be written, without creating it yet.
+    let directory = Path::new(&options.output)
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    if !directory.is_dir() {
+        return Err(ExportError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("`{}` is not a directory", directory.display()),
+        )));
+    }
+
+    let mut canvas = Canvas {
+        image: Image::gen_image_color(image_width, image_height, color_u8!(0, 0, 0, 255)),
+        panel: Rect::new(0.0, 0.0, f32::from(panel_width), f32::from(image_height)),
+        origin: vec2(0.0, 0.0),
+        pixels_per_unit: f32::from(options.cell_pixels) / config.chunk_tile_size,
+    };
+    let chunk_size = config.chunk_world_size();
+    for (panel, seed) in options.seeds.iter().enumerate() {
+        let panel = f32::from(u16::try_from(panel).unwrap_or(u16::MAX));
+        canvas.panel.x = (f32::from(panel_width) + f32::from(PANEL_GAP)) * panel;
+        let (origin_x, origin_y) = ChunkPosition::new(min_x, min_y).offsets(chunk_size);
+        canvas.origin = vec2(origin_x, origin_y);
+        canvas.fill_rect(
+            Rect::new(
+                origin_x,
+                origin_y,
+                canvas.panel.w / canvas.pixels_per_unit,
+                canvas.panel.h / canvas.pixels_per_unit,
+            ),
+            color_u8!(255, 255, 255, 255),
+        );
+
+        let mut noise = Noise::new();
+        noise.set_graph(&noise_graph, *seed, config.noise_frequency);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let mut chunk = Chunk::new(ChunkPosition::new(x, y), *config);
+                chunk.populate(&noise);
+                chunk.rasterize(&mut canvas);
+            }
+        }
+    }
+    // Only now that the image is done, so failures don't leave an empty file behind.
+    image::save_buffer_with_format(
+        &options.output,
+        &canvas.image.bytes,
+        u32::from(image_width),
+        u32::from(image_height),
+        ColorType::Rgba8,
+        ImageFormat::Png,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_regions_are_rejected_before_generating() {
+        let config = WorldConfig::default();
+        for (from, to, cell_pixels) in [
+            (
+                ChunkPosition::new(0, 0),
+                ChunkPosition::new(10_000, 0),
+                u16::MAX,
+            ),
+            (
+                ChunkPosition::new(i32::MIN, 0),
+                ChunkPosition::new(i32::MAX, 0),
+                1,
+            ),
+            (ChunkPosition::new(0, -400), ChunkPosition::new(0, 400), 16),
+        ] {
+            let options = ExportOptions {
+                from,
+                to,
+                cell_pixels,
+                ..ExportOptions::default()
+            };
+            assert!(matches!(
+                export_region(&options, &config),
+                Err(ExportError::TooLarge { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn regions_are_written_as_png() {
+        let path = std::env::temp_dir().join(format!("venture_export_{}.png", std::process::id()));
+        let options = ExportOptions {
+            from: ChunkPosition::new(0, 0),
+            to: ChunkPosition::new(1, 0),
+            seeds: vec![1, 2],
+            cell_pixels: 1,
+            output: path.to_str().expect("temp dir should be utf-8").to_owned(),
+            ..ExportOptions::default()
+        };
+        let config = WorldConfig {
+            chunk_size: 8,
+            noise_image_size: 128,
+            ..WorldConfig::default()
+        };
+        let result = export_region(&options, &config);
+        let written = image::open(&path);
+        std::fs::remove_file(&path).expect("export should be removed");
+        result.expect("export should succeed");
+        let written = written
+            .expect("export should be a readable image")
+            .to_rgba8();
+        // Two panels of two 8 pixel chunks with a gap between them.
+        assert_eq!(written.dimensions(), (16 + 8 + 16, 8));
+    }
+
+    #[test]
+    fn missing_directories_fail_without_writing() {
+        let options = ExportOptions {
+            output: "no/such/directory/world.png".to_owned(),
+            ..ExportOptions::default()
+        };
+        assert!(matches!(
+            export_region(&options, &WorldConfig::default()),
+            Err(ExportError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+use macroquad::math::{vec2, Rect, Vec2};
+
+/// The part of the world the camera sees: a rectangle around `center` that turns with
+/// the camera, optionally limited further by an axis-aligned `clip` rect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewFrustum {
+    center: Vec2,
+    half_size: Vec2,
+    /// In radians, like `Camera::rotation`.
+    rotation: f32,
+    clip: Option<Rect>,
+}
+
+impl ViewFrustum {
+    #[must_use]
+    pub fn new(center: Vec2, half_size: Vec2, rotation: f32) -> Self {
+        Self {
+            center,
+            half_size: half_size.abs(),
+            rotation,
+            clip: None,
+        }
+    }
+
+    /// Only things that also touch `clip` are visible, e.g. when fog hides the rest.
+    #[must_use]
+    pub fn with_clip(self, clip: Rect) -> Self {
+        let clip = self
+            .clip
+            .map_or(Some(clip), |old| old.intersect(clip))
+            .unwrap_or_else(|| Rect::new(clip.x, clip.y, 0.0, 0.0));
+        Self {
+            clip: Some(clip),
+            ..self
+        }
+    }
+
+    /// Corners of the rotated view, clockwise from the top left.
+    #[must_use]
+    pub fn corners(&self) -> [Vec2; 4] {
+        let (x_axis, y_axis) = self.axes();
+        let (x, y) = (x_axis * self.half_size.x, y_axis * self.half_size.y);
+        [
+            self.center - x - y,
+            self.center + x - y,
+            self.center + x + y,
+            self.center - x + y,
+        ]
+    }
+
+    /// Axis-aligned box around everything that can be visible.
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        let corners = self.corners();
+        let min = corners
+            .iter()
+            .fold(corners[0], |min, corner| min.min(*corner));
+        let max = corners
+            .iter()
+            .fold(corners[0], |max, corner| max.max(*corner));
+        let bounds = Rect::new(min.x, min.y, max.x - min.x, max.y - min.y);
+        self.clip.map_or(bounds, |clip| {
+            bounds
+                .intersect(clip)
+                .unwrap_or_else(|| Rect::new(clip.x, clip.y, 0.0, 0.0))
+        })
+    }
+
+    /// Whether anything inside `rect` can be visible.
+    #[must_use]
+    pub fn intersects(&self, rect: Rect) -> bool {
+        if !overlaps(self.bounds(), rect) {
+            return false;
+        }
+        // Separating axis test on the view's own axes, the world axes were covered by
+        // the bounds above.
+        let rect_center = vec2(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0);
+        let rect_half = vec2(rect.w / 2.0, rect.h / 2.0);
+        let offset = rect_center - self.center;
+        let (x_axis, y_axis) = self.axes();
+        [(x_axis, self.half_size.x), (y_axis, self.half_size.y)]
+            .iter()
+            .all(|&(axis, half_extent)| {
+                let rect_extent = rect_half
+                    .x
+                    .mul_add(axis.x.abs(), rect_half.y * axis.y.abs());
+                offset.dot(axis).abs() <= half_extent + rect_extent
+            })
+    }
+
+    fn axes(&self) -> (Vec2, Vec2) {
+        let (sin, cos) = self.rotation.sin_cos();
+        (vec2(cos, sin), vec2(-sin, cos))
+    }
+}
+
+/// Like `Rect::overlaps`, but touching edges and empty rects count.
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.left() <= b.right() && a.right() >= b.left() && a.top() <= b.bottom() && a.bottom() >= b.top()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    #[test]
+    fn unrotated_view_matches_its_rect() {
+        let view = ViewFrustum::new(vec2(100.0, 50.0), vec2(20.0, 10.0), 0.0);
+        assert_eq!(view.bounds(), Rect::new(80.0, 40.0, 40.0, 20.0));
+        assert!(view.intersects(Rect::new(115.0, 55.0, 10.0, 10.0)));
+        assert!(!view.intersects(Rect::new(125.0, 55.0, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn rotated_view_culls_the_corners_of_its_bounds() {
+        let view = ViewFrustum::new(Vec2::ZERO, vec2(10.0, 10.0), FRAC_PI_4);
+        let bounds = view.bounds();
+        assert!((bounds.w - 800.0_f32.sqrt()).abs() < 0.001);
+        // Inside the bounds, but past the rotated edge.
+        assert!(!view.intersects(Rect::new(10.0, 10.0, 2.0, 2.0)));
+        assert!(view.intersects(Rect::new(12.0, -1.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn clip_limits_the_view() {
+        let view = ViewFrustum::new(Vec2::ZERO, vec2(100.0, 100.0), 0.0)
+            .with_clip(Rect::new(-10.0, -10.0, 20.0, 20.0));
+        assert!(view.intersects(Rect::new(5.0, 5.0, 1.0, 1.0)));
+        assert!(!view.intersects(Rect::new(50.0, 50.0, 1.0, 1.0)));
+    }
+}
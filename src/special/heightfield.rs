@@ -0,0 +1,186 @@
+use std::ops::RangeInclusive;
+
+use macroquad::color::Color;
+use macroquad::math::{vec2, vec3, Vec2};
+use macroquad::models::{Mesh, Vertex};
+use macroquad::texture::Image;
+
+use crate::special::config::WorldConfig;
+use crate::special::noise::Noise;
+
+/// Cells along each side of a terrain mesh tile, the most that fit a draw call.
+const TILE_CELLS: u16 = 28;
+
+/// Noise values sampled on a square grid of world positions, in the range 0-255.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heightfield {
+    size: u16,
+    values: Vec<f32>,
+}
+
+impl Heightfield {
+    /// Samples `size` x `size` points starting at `origin`, `spacing` world units apart.
+    #[must_use]
+    pub fn sample(
+        noise: &Noise,
+        config: &WorldConfig,
+        size: u16,
+        origin: Vec2,
+        spacing: f32,
+    ) -> Self {
+        let mut values = Vec::with_capacity(usize::from(size) * usize::from(size));
+        for y in 0..size {
+            for x in 0..size {
+                let offset = vec2(f32::from(x), f32::from(y)) * spacing;
+                values.push(noise.sample_world(origin + offset, config));
+            }
+        }
+        Self { size, values }
+    }
+
+    #[must_use]
+    pub const fn size(&self) -> u16 {
+        self.size
+    }
+
+    #[must_use]
+    pub fn get(&self, x: u16, y: u16) -> f32 {
+        let index = usize::from(y) * usize::from(self.size) + usize::from(x);
+        *self
+            .values
+            .get(index)
+            .expect("heightfield point should be inside the grid")
+    }
+
+    /// Grid meshes with a vertex on every point, placed like in `sample` and coloured by
+    /// `color`. The GPU interpolates the colours between the points. The grid is split into
+    /// tiles that each fit a draw call, neighbouring tiles share their edge points.
+    #[must_use]
+    pub fn to_meshes(&self, origin: Vec2, spacing: f32, color: impl Fn(f32) -> Color) -> Vec<Mesh> {
+        let cells = self.size.saturating_sub(1);
+        let mut meshes = Vec::new();
+        for top in (0..cells).step_by(usize::from(TILE_CELLS)) {
+            for left in (0..cells).step_by(usize::from(TILE_CELLS)) {
+                let right = left.saturating_add(TILE_CELLS).min(cells);
+                let bottom = top.saturating_add(TILE_CELLS).min(cells);
+                meshes.push(self.tile_mesh(left..=right, top..=bottom, origin, spacing, &color));
+            }
+        }
+        meshes
+    }
+
+    /// The mesh of the points in `columns` x `rows`.
+    fn tile_mesh(
+        &self,
+        columns: RangeInclusive<u16>,
+        rows: RangeInclusive<u16>,
+        origin: Vec2,
+        spacing: f32,
+        color: &impl Fn(f32) -> Color,
+    ) -> Mesh {
+        let mut vertices = Vec::new();
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let position = origin + vec2(f32::from(x), f32::from(y)) * spacing;
+                vertices.push(Vertex {
+                    position: vec3(position.x, position.y, 0.0),
+                    uv: vec2(0.0, 0.0),
+                    color: color(self.get(x, y)),
+                });
+            }
+        }
+        let width = columns.end() - columns.start() + 1;
+        let cells_x = width - 1;
+        let cells_y = rows.end() - rows.start();
+        let mut indices = Vec::with_capacity(usize::from(cells_x) * usize::from(cells_y) * 6);
+        for y in 0..cells_y {
+            for x in 0..cells_x {
+                let top_left = y * width + x;
+                let bottom_left = top_left + width;
+                indices.extend_from_slice(&[
+                    top_left,
+                    top_left + 1,
+                    bottom_left,
+                    bottom_left,
+                    top_left + 1,
+                    bottom_left + 1,
+                ]);
+            }
+        }
+        Mesh {
+            vertices,
+            indices,
+            texture: None,
+        }
+    }
+
+    /// Grayscale image with one pixel per point.
+    #[must_use]
+    pub fn to_image(&self) -> Image {
+        let mut image =
+            Image::gen_image_color(self.size, self.size, Color::new(0.0, 0.0, 0.0, 1.0));
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let value = self.get(x, y) / 255.0;
+                image.set_pixel(
+                    u32::from(x),
+                    u32::from(y),
+                    Color::new(value, value, value, 1.0),
+                );
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::WHITE;
+
+    use super::*;
+    use crate::special::batch::{MAX_MESH_INDICES, MAX_MESH_VERTICES};
+
+    /// Every value encodes its point as `x + 1000 * y`.
+    fn heightfield(size: u16) -> Heightfield {
+        let mut values = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                values.push(f32::from(y).mul_add(1000.0, f32::from(x)));
+            }
+        }
+        Heightfield { size, values }
+    }
+
+    #[test]
+    fn meshes_cover_every_cell_within_a_draw_call() {
+        for size in [2, 17, 29, 30, 33, 255] {
+            let meshes = heightfield(size).to_meshes(vec2(-5.0, 3.0), 2.0, |_| WHITE);
+            let cells = usize::from(size - 1).pow(2);
+            let indices: usize = meshes.iter().map(|mesh| mesh.indices.len()).sum();
+            assert_eq!(indices, cells * 6, "size {size}");
+            for mesh in &meshes {
+                assert!(mesh.vertices.len() < MAX_MESH_VERTICES);
+                assert!(mesh.indices.len() < MAX_MESH_INDICES);
+                assert!(mesh
+                    .indices
+                    .iter()
+                    .all(|&index| usize::from(index) < mesh.vertices.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_keep_the_points_in_place() {
+        let meshes = heightfield(33).to_meshes(vec2(-5.0, 3.0), 2.0, |value| {
+            Color::new(value % 1000.0, (value / 1000.0).floor(), 0.0, 1.0)
+        });
+        assert!(meshes.len() > 1);
+        for vertex in meshes.iter().flat_map(|mesh| &mesh.vertices) {
+            let (x, y) = (vertex.color.r, vertex.color.g);
+            assert_eq!(
+                vertex.position,
+                vec3(x.mul_add(2.0, -5.0), y.mul_add(2.0, 3.0), 0.0)
+            );
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use macroquad::color::Color;
+use macroquad::color_u8;
+use macroquad::input::{is_key_pressed, KeyCode};
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::shapes::draw_rectangle_lines;
+use macroquad::ui::{hash, root_ui, widgets::Window};
+use macroquad::window::screen_width;
+
+use indexmap::IndexMap;
+
+use crate::entity::inspect::{Inspect, Property, PropertyValue};
+use crate::entity::statich::Static;
+use crate::special::chunk::Chunk;
+use crate::special::config::WorldConfig;
+use crate::world::ChunkPosition;
+
+/// How far a dynamic entity can move between two frames and still be recognised as the
+/// selected one.
+const TRACK_DISTANCE: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selected {
+    /// Index into `Chunk::statics`.
+    Static(usize),
+    /// Index into `Chunk::dynamics`.
+    Dynamic(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Selection {
+    pub chunk: ChunkPosition,
+    pub entity: Selected,
+    /// Where the entity was last seen, dynamics are found again from here every frame.
+    position: Vec2,
+}
+
+/// Click an entity to select it, the panel shows what it is and lets its properties be
+/// edited while the game runs.
+pub struct Inspector {
+    selection: Option<Selection>,
+}
+
+impl Inspector {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { selection: None }
+    }
+
+    #[must_use]
+    pub const fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    pub const fn clear(&mut self) {
+        self.selection = None;
+    }
+
+    /// Whether `screen_point` is over the inspector panel, or any other ui window.
+    #[must_use]
+    pub fn is_mouse_over_ui(screen_point: Vec2) -> bool {
+        root_ui().is_mouse_over(screen_point)
+    }
+
+    /// Selects the entity under `point` nearest to it. Anything standing on the terrain
+    /// wins over the terrain itself. Points too far out for a chunk pick nothing.
+    pub fn pick(
+        &mut self,
+        chunks: &IndexMap<ChunkPosition, Chunk>,
+        config: &WorldConfig,
+        point: Vec2,
+    ) {
+        self.selection = None;
+        let Some(center) = ChunkPosition::checked_from_world(point, config.chunk_world_size())
+        else {
+            return;
+        };
+        let mut best: Option<((bool, f32), Selection)> = None;
+        let mut consider = |is_terrain: bool, bounds: Rect, position: Vec2, selection| {
+            if !contains(bounds, point) {
+                return;
+            }
+            let key = (is_terrain, position.distance(point));
+            if best.as_ref().is_none_or(|&(best_key, _)| key < best_key) {
+                best = Some((key, selection));
+            }
+        };
+        for (&chunk_position, chunk) in chunks {
+            // Entities can stick out of their chunk, so the neighbours count too.
+            if !chunk_position.is_within(center, 1) {
+                continue;
+            }
+            for (index, static_entity) in chunk.statics().iter().enumerate() {
+                let position = static_entity.position();
+                consider(
+                    matches!(*static_entity, Static::Terrain(_)),
+                    static_entity.bounds(),
+                    position,
+                    Selection {
+                        chunk: chunk_position,
+                        entity: Selected::Static(index),
+                        position,
+                    },
+                );
+            }
+            for (index, dynamic) in chunk.dynamics.iter().enumerate() {
+                let position = dynamic.position;
+                consider(
+                    false,
+                    dynamic.bounds(),
+                    position,
+                    Selection {
+                        chunk: chunk_position,
+                        entity: Selected::Dynamic(index),
+                        position,
+                    },
+                );
+            }
+        }
+        self.selection = best.map(|(_, selection)| selection);
+    }
+
+    /// Finds the selected entity again after the world updated, dynamics move around
+    /// and change chunks. Drops the selection when it is gone.
+    pub fn refresh(&mut self, chunks: &IndexMap<ChunkPosition, Chunk>, config: &WorldConfig) {
+        let Some(selection) = self.selection else {
+            return;
+        };
+        self.selection = match selection.entity {
+            Selected::Static(index) => chunks
+                .get(&selection.chunk)
+                .filter(|chunk| index < chunk.statics().len())
+                .map(|_| selection),
+            Selected::Dynamic(_) => track_dynamic(chunks, config, selection.position),
+        };
+    }
+
+    /// Outlines the selected entity, in world space.
+    pub fn draw_highlight(&self, chunks: &IndexMap<ChunkPosition, Chunk>) {
+        let Some(bounds) = self
+            .selection
+            .and_then(|selection| selected_bounds(chunks, selection))
+        else {
+            return;
+        };
+        let margin = 4.0;
+        draw_rectangle_lines(
+            bounds.x - margin,
+            bounds.y - margin,
+            bounds.w + margin * 2.0,
+            bounds.h + margin * 2.0,
+            6.0,
+            color_u8!(255, 220, 60, 255),
+        );
+    }
+
+    /// Shows the inspector panel for the selection and applies edits to it.
+    pub fn ui(&mut self, chunks: &mut IndexMap<ChunkPosition, Chunk>) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.clear();
+        }
+        let Some(selection) = self.selection else {
+            return;
+        };
+        let Some(chunk) = chunks.get_mut(&selection.chunk) else {
+            return;
+        };
+        let Some((kind, properties)) = describe(chunk, selection.entity) else {
+            return;
+        };
+
+        let size = vec2(300.0, 240.0);
+        let mut edits = Vec::new();
+        let mut deselect = false;
+        Window::new(hash!(), vec2(screen_width() - size.x - 10.0, 230.0), size)
+            .label("Inspector")
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, &format!("type: {kind}"));
+                ui.label(None, &format!("chunk: {}", selection.chunk));
+                for property in &properties {
+                    match property.value {
+                        PropertyValue::Number(value) => {
+                            let mut edited = value;
+                            ui.drag(
+                                hash!("inspector", property.name),
+                                property.name,
+                                None,
+                                &mut edited,
+                            );
+                            if (edited - value).abs() > f32::EPSILON {
+                                edits.push((property.name, edited));
+                            }
+                        }
+                        PropertyValue::Text(ref text) => {
+                            ui.label(None, &format!("{}: {}", property.name, text));
+                        }
+                    }
+                }
+                deselect = ui.button(None, "deselect");
+            });
+        if deselect {
+            self.clear();
+            return;
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        match selection.entity {
+            Selected::Static(index) => {
+                if let Some(static_entity) = chunk.static_mut(index) {
+                    for (name, value) in edits {
+                        static_entity.inspect_mut().set_property(name, value);
+                    }
+                }
+            }
+            Selected::Dynamic(index) => {
+                if let Some(mut dynamic) = chunk.dynamics.get(index) {
+                    for (name, value) in edits {
+                        dynamic.set_property(name, value);
+                    }
+                    chunk.dynamics.set(index, dynamic);
+                    // Keep tracking it after being moved by hand.
+                    self.selection = Some(Selection {
+                        position: dynamic.position,
+                        ..selection
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Like `Rect::contains`, but points on the edges and zero sized rects count.
+fn contains(rect: Rect, point: Vec2) -> bool {
+    point.x >= rect.left()
+        && point.x <= rect.right()
+        && point.y >= rect.top()
+        && point.y <= rect.bottom()
+}
+
+fn track_dynamic(
+    chunks: &IndexMap<ChunkPosition, Chunk>,
+    config: &WorldConfig,
+    last: Vec2,
+) -> Option<Selection> {
+    let center = ChunkPosition::checked_from_world(last, config.chunk_world_size())?;
+    let mut best: Option<(f32, Selection)> = None;
+    for (&chunk_position, chunk) in chunks {
+        if !chunk_position.is_within(center, 1) {
+            continue;
+        }
+        for (index, dynamic) in chunk.dynamics.iter().enumerate() {
+            let position = dynamic.position;
+            let distance = position.distance(last);
+            if distance <= TRACK_DISTANCE && best.is_none_or(|(best, _)| distance < best) {
+                best = Some((
+                    distance,
+                    Selection {
+                        chunk: chunk_position,
+                        entity: Selected::Dynamic(index),
+                        position,
+                    },
+                ));
+            }
+        }
+    }
+    best.map(|(_, selection)| selection)
+}
+
+fn selected_bounds(chunks: &IndexMap<ChunkPosition, Chunk>, selection: Selection) -> Option<Rect> {
+    let chunk = chunks.get(&selection.chunk)?;
+    match selection.entity {
+        Selected::Static(index) => chunk.statics().get(index).map(Static::bounds),
+        Selected::Dynamic(index) => chunk.dynamics.get(index).map(|entity| entity.bounds()),
+    }
+}
+
+/// Kind and properties of the selected entity, dynamics are copied out of their store so
+/// they can't be lent out as `&dyn Inspect`.
+fn describe(chunk: &Chunk, selected: Selected) -> Option<(&'static str, Vec<Property>)> {
+    match selected {
+        Selected::Static(index) => chunk
+            .statics()
+            .get(index)
+            .map(|entity| (entity.inspect().kind(), entity.inspect().properties())),
+        Selected::Dynamic(index) => chunk
+            .dynamics
+            .get(index)
+            .map(|entity| (entity.kind(), entity.properties())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::special::noise::Noise;
+
+    fn chunks(chunk: Chunk) -> IndexMap<ChunkPosition, Chunk> {
+        let mut chunks = IndexMap::new();
+        chunks.insert(ChunkPosition::new(0, 0), chunk);
+        chunks
+    }
+
+    #[test]
+    fn nearest_entity_under_the_point_is_picked() {
+        let config = WorldConfig::default();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), config);
+        chunk.add_stone(vec2(100.0, 100.0), 0.0, 20.0);
+        chunk.add_stone(vec2(130.0, 100.0), 0.0, 20.0);
+        chunk.add_follower(vec2(400.0, 400.0));
+        let chunks = chunks(chunk);
+        let mut inspector = Inspector::new();
+
+        inspector.pick(&chunks, &config, vec2(120.0, 100.0));
+        assert_eq!(
+            inspector.selection().map(|selection| selection.entity),
+            Some(Selected::Static(1))
+        );
+        inspector.pick(&chunks, &config, vec2(401.0, 399.0));
+        assert_eq!(
+            inspector.selection().map(|selection| selection.entity),
+            Some(Selected::Dynamic(0))
+        );
+        inspector.pick(&chunks, &config, vec2(250.0, 250.0));
+        assert_eq!(inspector.selection(), None);
+    }
+
+    #[test]
+    fn entities_on_the_terrain_win_over_it() {
+        let config = WorldConfig {
+            chunk_size: 8,
+            ..WorldConfig::default()
+        };
+        let mut noise = Noise::new();
+        noise.set_noise(3, 0.005);
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), config);
+        chunk.populate(&noise);
+        let stone = chunk
+            .statics()
+            .iter()
+            .position(|entity| matches!(*entity, Static::Stone(_)))
+            .expect("the chunk should have a stone");
+        let stone_position = chunk.statics().get(stone).map(Static::position);
+        // A terrain cell corner nothing else covers.
+        let terrain_point = chunk
+            .statics()
+            .iter()
+            .filter(|entity| matches!(*entity, Static::Terrain(_)))
+            .map(|entity| entity.position() + vec2(0.5, 0.5))
+            .find(|&point| {
+                let statics = chunk
+                    .statics()
+                    .iter()
+                    .filter(|entity| !matches!(*entity, Static::Terrain(_)))
+                    .map(Static::bounds);
+                let mut dynamics = chunk.dynamics.iter().map(|entity| entity.bounds());
+                !statics
+                    .chain(&mut dynamics)
+                    .any(|bounds| contains(bounds, point))
+            });
+        let chunks = chunks(chunk);
+        let mut inspector = Inspector::new();
+
+        let picked_terrain = |inspector: &Inspector| {
+            let selection = inspector.selection()?;
+            let Selected::Static(index) = selection.entity else {
+                return Some(false);
+            };
+            chunks
+                .get(&selection.chunk)?
+                .statics()
+                .get(index)
+                .map(|entity| matches!(*entity, Static::Terrain(_)))
+        };
+        inspector.pick(&chunks, &config, stone_position.expect("stone"));
+        assert_eq!(picked_terrain(&inspector), Some(false));
+        inspector.pick(&chunks, &config, terrain_point.expect("open terrain"));
+        assert_eq!(picked_terrain(&inspector), Some(true));
+    }
+
+    #[test]
+    fn points_without_a_chunk_pick_nothing() {
+        let config = WorldConfig::default();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), config);
+        chunk.add_stone(vec2(100.0, 100.0), 0.0, 20.0);
+        let chunks = chunks(chunk);
+        let mut inspector = Inspector::new();
+
+        inspector.pick(&chunks, &config, vec2(100.0, 100.0));
+        assert!(inspector.selection().is_some());
+        for point in [vec2(f32::MAX, 0.0), vec2(0.0, -1e30), vec2(f32::NAN, 0.0)] {
+            inspector.pick(&chunks, &config, point);
+            assert_eq!(inspector.selection(), None);
+        }
+    }
+}
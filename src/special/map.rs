@@ -0,0 +1,264 @@
+use macroquad::color::Color;
+use macroquad::color_u8;
+use macroquad::input::{
+    is_key_pressed, is_mouse_button_down, mouse_position, mouse_wheel, KeyCode, MouseButton,
+};
+use macroquad::math::{vec2, Mat3, Rect, Vec2};
+use macroquad::shapes::{draw_circle, draw_line, draw_rectangle, draw_rectangle_lines};
+use macroquad::text::draw_text;
+use macroquad::window::{screen_height, screen_width};
+
+use indexmap::IndexMap;
+
+use crate::entity::statich::Static;
+use crate::special::chunk::Chunk;
+use crate::special::config::WorldConfig;
+use crate::special::exploration::Exploration;
+use crate::special::noise::Noise;
+use crate::special::square::Square;
+use crate::world::ChunkPosition;
+
+const MINIMAP_SIZE: f32 = 200.0;
+const MINIMAP_MARGIN: f32 = 10.0;
+/// World units per screen pixel on the minimap.
+const MINIMAP_SCALE: f32 = 60.0;
+const MIN_MAP_SCALE: f32 = 5.0;
+const MAX_MAP_SCALE: f32 = 1000.0;
+
+/// Minimap and toggleable full-screen map.
+///
+/// Both are drawn in screen space from the chunks' heightfields and have their own
+/// center and zoom, separate from the main camera. Cells the player hasn't seen yet stay hidden.
+pub struct WorldMap {
+    pub open: bool,
+    pub center: Vec2,
+    /// World units per screen pixel.
+    pub scale: f32,
+    /// Draw the full map from the noise at full resolution instead of one texel per cell.
+    pub detailed: bool,
+    last_mouse: Option<Vec2>,
+}
+
+impl WorldMap {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            open: false,
+            center: Vec2::ZERO,
+            scale: 100.0,
+            detailed: false,
+            last_mouse: None,
+        }
+    }
+
+    pub fn input(&mut self, player: Vec2) {
+        if is_key_pressed(KeyCode::M) {
+            self.open = !self.open;
+            self.center = player;
+        }
+        if !self.open {
+            return;
+        }
+        if is_key_pressed(KeyCode::C) {
+            self.center = player;
+        }
+        if is_key_pressed(KeyCode::N) {
+            self.detailed = !self.detailed;
+        }
+
+        let mouse = mouse_position();
+        let mouse = vec2(mouse.0, mouse.1);
+        if is_mouse_button_down(MouseButton::Left) {
+            if let Some(last_mouse) = self.last_mouse {
+                self.center -= (mouse - last_mouse) * self.scale;
+            }
+            self.last_mouse = Some(mouse);
+        } else {
+            self.last_mouse = None;
+        }
+
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 {
+            let factor = if wheel > 0.0 { 0.8 } else { 1.25 };
+            self.scale = (self.scale * factor).clamp(MIN_MAP_SCALE, MAX_MAP_SCALE);
+        }
+    }
+
+    pub fn draw_minimap(
+        &self,
+        chunks: &IndexMap<ChunkPosition, Chunk>,
+        config: &WorldConfig,
+        exploration: &Exploration,
+        player: &Square,
+    ) {
+        let area = Rect::new(
+            screen_width() - MINIMAP_SIZE - MINIMAP_MARGIN,
+            MINIMAP_MARGIN,
+            MINIMAP_SIZE,
+            MINIMAP_SIZE,
+        );
+        let projection = MapProjection {
+            area,
+            center: player.center,
+            scale: MINIMAP_SCALE,
+        };
+        draw_area(chunks, config, exploration, &projection, player, None);
+    }
+
+    /// `noise` is the terrain noise, used when the detailed view is on.
+    pub fn draw(
+        &self,
+        chunks: &IndexMap<ChunkPosition, Chunk>,
+        config: &WorldConfig,
+        exploration: &Exploration,
+        player: &Square,
+        noise: Option<&Noise>,
+    ) {
+        if !self.open {
+            return;
+        }
+        let (width, height) = (screen_width(), screen_height());
+        let area = Rect::new(width * 0.1, height * 0.1, width * 0.8, height * 0.8);
+        let projection = MapProjection {
+            area,
+            center: self.center,
+            scale: self.scale,
+        };
+        let noise = noise.filter(|_| self.detailed);
+        draw_area(chunks, config, exploration, &projection, player, noise);
+        draw_text(
+            "map: drag to pan, wheel to zoom, C to center, N for detail, M to close",
+            area.x,
+            area.y - 10.0,
+            20.0,
+            color_u8!(200, 200, 200, 255),
+        );
+    }
+}
+
+impl Default for WorldMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps world positions into a screen-space `area` centered on `center`.
+struct MapProjection {
+    area: Rect,
+    center: Vec2,
+    scale: f32,
+}
+
+impl MapProjection {
+    fn to_screen(&self, world: Vec2) -> Vec2 {
+        let area_center = vec2(
+            self.area.x + self.area.w / 2.0,
+            self.area.y + self.area.h / 2.0,
+        );
+        area_center + (world - self.center) / self.scale
+    }
+}
+
+fn draw_area(
+    chunks: &IndexMap<ChunkPosition, Chunk>,
+    config: &WorldConfig,
+    exploration: &Exploration,
+    projection: &MapProjection,
+    player: &Square,
+    noise: Option<&Noise>,
+) {
+    let area = projection.area;
+    let unexplored = color_u8!(20, 20, 25, 230);
+    draw_rectangle(area.x, area.y, area.w, area.h, unexplored);
+
+    let chunk_size = config.chunk_world_size();
+    let cell_size = config.chunk_tile_size / projection.scale;
+    for (pos, chunk) in chunks {
+        let explored = match exploration.chunk(*pos) {
+            Some(explored) if explored.count() > 0 => explored,
+            _ => continue,
+        };
+        let (x, y) = pos.offsets(chunk_size);
+        let top_left = projection.to_screen(vec2(x, y));
+        let dest = Rect::new(
+            top_left.x,
+            top_left.y,
+            chunk_size / projection.scale,
+            chunk_size / projection.scale,
+        );
+        let Some(visible) = dest.intersect(area) else {
+            continue;
+        };
+        match noise {
+            Some(noise) => chunk.draw_noise_texture(noise, visible, dest),
+            None => chunk.draw_height_texture(visible, dest),
+        }
+
+        // Cover the cells that haven't been seen yet, one rectangle per row run.
+        let cells = config.chunk_size;
+        let is_explored = |x: u16, y: u16| explored.is_explored(usize::from(x), usize::from(y));
+        for cell_y in 0..cells {
+            let mut cell_x = 0;
+            while cell_x < cells {
+                if is_explored(cell_x, cell_y) {
+                    cell_x += 1;
+                    continue;
+                }
+                let run_start = cell_x;
+                while cell_x < cells && !is_explored(cell_x, cell_y) {
+                    cell_x += 1;
+                }
+                let run = Rect::new(
+                    f32::from(run_start).mul_add(cell_size, dest.x),
+                    f32::from(cell_y).mul_add(cell_size, dest.y),
+                    f32::from(cell_x - run_start) * cell_size,
+                    cell_size,
+                );
+                if let Some(run) = run.intersect(area) {
+                    draw_rectangle(run.x, run.y, run.w, run.h, unexplored);
+                }
+            }
+        }
+    }
+
+    let road_color = color_u8!(200, 170, 90, 255);
+    for chunk in chunks.values() {
+        for static_entity in chunk.statics() {
+            if let Static::Road(ref segment) = *static_entity {
+                let (start, end) = segment.endpoints();
+                if !exploration.is_explored(start, config) && !exploration.is_explored(end, config)
+                {
+                    continue;
+                }
+                let (start, end) = (projection.to_screen(start), projection.to_screen(end));
+                if area.contains(start) && area.contains(end) {
+                    draw_line(start.x, start.y, end.x, end.y, 2.0, road_color);
+                }
+            }
+        }
+    }
+
+    let player_on_map = projection.to_screen(player.center);
+    if area.contains(player_on_map) {
+        let facing = Mat3::from_rotation_z(player.rotation).transform_vector2(vec2(0.0, 8.0));
+        let marker = color_u8!(255, 60, 60, 255);
+        draw_circle(player_on_map.x, player_on_map.y, 4.0, marker);
+        draw_line(
+            player_on_map.x,
+            player_on_map.y,
+            player_on_map.x + facing.x,
+            player_on_map.y + facing.y,
+            2.0,
+            marker,
+        );
+    }
+
+    draw_rectangle_lines(
+        area.x,
+        area.y,
+        area.w,
+        area.h,
+        2.0,
+        color_u8!(200, 200, 200, 255),
+    );
+}
@@ -1,5 +1,22 @@
+pub mod batch;
+pub mod bench;
 pub mod camera;
 pub mod chunk;
+pub mod config;
+pub mod console;
+pub mod creation;
+pub mod exploration;
+pub mod export;
+pub mod frustum;
+pub mod heightfield;
+pub mod inspector;
+pub mod map;
 pub mod noise;
+pub mod noise_graph;
+pub mod overlay;
+pub mod parallel;
 pub mod profiler;
+pub mod save;
 pub mod square;
+pub mod weather;
+pub mod zones;
@@ -1,66 +1,71 @@
-use macroquad::color::Color;
-use macroquad::color_u8;
-use macroquad::texture::Image;
+use macroquad::math::Vec2;
 
 use once_cell::sync::OnceCell;
-use simple_simplex::NoiseConfig;
+
+use crate::special::config::WorldConfig;
+use crate::special::noise_graph::{NoiseGraph, NoiseNode};
+
+/// Converts a world position into noise space. Every sampler goes through here, so the
+/// noise lines up across chunk borders whatever the chunk sizes are.
+#[must_use]
+pub fn world_to_noise(world: Vec2, config: &WorldConfig) -> Vec2 {
+    world * config.noise_scale()
+}
 
 pub struct Noise {
-    noise: OnceCell<NoiseConfig>,
+    graph: OnceCell<NoiseGraph>,
+    seed: u64,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Noise {
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            noise: OnceCell::new(),
+            graph: OnceCell::new(),
+            seed: 0,
         }
     }
 
+    /// Uses the default single layer noise graph.
     pub fn set_noise(&mut self, seed: u64, frequency: f32) {
-        let new_noise = Self::gen_noise(seed, frequency);
-        if let Err(new_noise) = self.noise.set(new_noise) {
+        self.set_graph(&NoiseNode::default(), seed, frequency);
+    }
+
+    pub fn set_graph(&mut self, node: &NoiseNode, seed: u64, frequency: f32) {
+        self.seed = seed;
+        let new_graph = node.build(seed, frequency);
+        if let Err(new_graph) = self.graph.set(new_graph) {
             *self
-                .noise
+                .graph
                 .get_mut()
-                .expect("Noise config cell should be full.") = new_noise;
+                .expect("Noise graph cell should be full.") = new_graph;
         }
     }
 
-    pub fn get(&self) -> &NoiseConfig {
-        self.noise.get().expect("tried to get uninitialized noise")
+    /// What the graph was built from, chunks seed their contents with it too.
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
     }
 
-    pub fn get_point(&self, x: f32, y: f32) -> f32 {
-        let noise = self.get();
-        noise.generate_range(x, y)
+    pub fn get(&self) -> &NoiseGraph {
+        self.graph.get().expect("tried to get uninitialized noise")
     }
 
-    fn gen_noise(seed: u64, frequency: f32) -> NoiseConfig {
-        NoiseConfig::new(
-            4,            // Octaves
-            frequency,    // X-Frequency
-            frequency,    // Y-Frequency
-            0.05,         // Amplitude
-            3.0,          // Lacunarity
-            0.25,         // Gain
-            (0.0, 255.0), // range
-            seed,         // seed
-        )
+    /// Noise value in the range 0-255, `x` and `y` are in noise space.
+    pub fn get_point(&self, x: f32, y: f32) -> f32 {
+        self.get().sample(x, y) * 255.0
     }
 
-    #[must_use]
-    pub fn gen_image(size: u16, xoff: f32, yoff: f32, noise: &NoiseConfig) -> Image {
-        let mut image = Image::gen_image_color(size, size, color_u8!(255, 0, 255, 255));
-
-        for y in 0..size {
-            for x in 0..size {
-                let color: u8 =
-                    noise.generate_range(xoff + f32::from(x), yoff + f32::from(y)) as u8;
-                let color = color_u8!(color, color, color, 255);
-                image.set_pixel(u32::from(x), u32::from(y), color);
-            }
-        }
-        image
+    /// Noise value in the range 0-255 at a world position.
+    pub fn sample_world(&self, world: Vec2, config: &WorldConfig) -> f32 {
+        let point = world_to_noise(world, config);
+        self.get_point(point.x, point.y)
     }
 }
@@ -0,0 +1,348 @@
+use std::fmt::Display;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use simple_simplex::NoiseConfig;
+
+use crate::common::map;
+
+/// Fractal simplex noise, normalised to `0.0..=1.0`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Simplex {
+    /// Added to the world seed, so layers with the same parameters can still differ.
+    pub seed: u64,
+    /// Relative to the world's noise frequency.
+    pub frequency: f32,
+    pub octaves: i32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Default for Simplex {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            frequency: 1.0,
+            octaves: 4,
+            lacunarity: 3.0,
+            gain: 0.25,
+        }
+    }
+}
+
+/// One node of a noise graph, as written in a config file. Every node outputs values
+/// that are roughly in `0.0..=1.0`, the graph's output is clamped to that range.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NoiseNode {
+    Simplex(Simplex),
+    /// Sharp crests where the source crosses the middle: ridges, or rivers when inverted.
+    Ridged(Box<Self>),
+    /// Rounded bumps with sharp creases at the middle of the source.
+    Billow(Box<Self>),
+    Constant(f32),
+    Add(Vec<Self>),
+    Multiply(Vec<Self>),
+    Clamp {
+        source: Box<Self>,
+        min: f32,
+        max: f32,
+    },
+    /// Linearly maps `from` onto `to`, `from: (0.0, 1.0), to: (1.0, 0.0)` inverts.
+    Remap {
+        source: Box<Self>,
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    /// Samples `source` at a position displaced by the `x` and `y` nodes, by up to
+    /// `strength` noise units in each direction.
+    Warp {
+        source: Box<Self>,
+        x: Box<Self>,
+        y: Box<Self>,
+        strength: f32,
+    },
+}
+
+impl Default for NoiseNode {
+    /// A single fractal simplex layer, the terrain generator before graphs were configurable.
+    fn default() -> Self {
+        Self::Simplex(Simplex::default())
+    }
+}
+
+impl NoiseNode {
+    /// # Errors
+    ///
+    /// Fails if `path` can't be read or doesn't describe a noise graph.
+    pub fn load(path: &str) -> Result<Self, NoiseGraphError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    /// Creates the evaluable graph. `frequency` is the world's base noise frequency,
+    /// simplex frequencies are relative to it.
+    #[must_use]
+    pub fn build(&self, seed: u64, frequency: f32) -> NoiseGraph {
+        let build = |node: &Self| Box::new(node.build(seed, frequency));
+        let build_all = |nodes: &[Self]| {
+            nodes
+                .iter()
+                .map(|node| node.build(seed, frequency))
+                .collect()
+        };
+        match *self {
+            Self::Simplex(ref simplex) => {
+                let frequency = simplex.frequency * frequency;
+                NoiseGraph::Simplex(Box::new(NoiseConfig::new(
+                    simplex.octaves,
+                    frequency,
+                    frequency,
+                    0.05,
+                    simplex.lacunarity,
+                    simplex.gain,
+                    (0.0, 1.0),
+                    seed.wrapping_add(simplex.seed),
+                )))
+            }
+            Self::Ridged(ref source) => NoiseGraph::Ridged(build(source)),
+            Self::Billow(ref source) => NoiseGraph::Billow(build(source)),
+            Self::Constant(value) => NoiseGraph::Constant(value),
+            Self::Add(ref nodes) => NoiseGraph::Add(build_all(nodes)),
+            Self::Multiply(ref nodes) => NoiseGraph::Multiply(build_all(nodes)),
+            Self::Clamp {
+                ref source,
+                min,
+                max,
+            } => NoiseGraph::Clamp {
+                source: build(source),
+                min,
+                max,
+            },
+            Self::Remap {
+                ref source,
+                from,
+                to,
+            } => NoiseGraph::Remap {
+                source: build(source),
+                from,
+                to,
+            },
+            Self::Warp {
+                ref source,
+                ref x,
+                ref y,
+                strength,
+            } => NoiseGraph::Warp {
+                source: build(source),
+                x: build(x),
+                y: build(y),
+                strength,
+            },
+        }
+    }
+}
+
+/// A noise graph ready to be sampled, see [`NoiseNode`] for what the nodes do.
+pub enum NoiseGraph {
+    Simplex(Box<NoiseConfig>),
+    Ridged(Box<Self>),
+    Billow(Box<Self>),
+    Constant(f32),
+    Add(Vec<Self>),
+    Multiply(Vec<Self>),
+    Clamp {
+        source: Box<Self>,
+        min: f32,
+        max: f32,
+    },
+    Remap {
+        source: Box<Self>,
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    Warp {
+        source: Box<Self>,
+        x: Box<Self>,
+        y: Box<Self>,
+        strength: f32,
+    },
+}
+
+impl NoiseGraph {
+    /// Samples the graph, clamped to `0.0..=1.0`.
+    #[must_use]
+    pub fn sample(&self, x: f32, y: f32) -> f32 {
+        self.evaluate(x, y).clamp(0.0, 1.0)
+    }
+
+    fn evaluate(&self, x: f32, y: f32) -> f32 {
+        match *self {
+            Self::Simplex(ref config) => config.generate_range(x, y),
+            Self::Ridged(ref source) => 1.0 - source.evaluate(x, y).mul_add(2.0, -1.0).abs(),
+            Self::Billow(ref source) => source.evaluate(x, y).mul_add(2.0, -1.0).abs(),
+            Self::Constant(value) => value,
+            Self::Add(ref nodes) => nodes.iter().map(|node| node.evaluate(x, y)).sum(),
+            Self::Multiply(ref nodes) => nodes.iter().map(|node| node.evaluate(x, y)).product(),
+            Self::Clamp {
+                ref source,
+                min,
+                max,
+            } => source.evaluate(x, y).clamp(min, max),
+            Self::Remap {
+                ref source,
+                from,
+                to,
+            } => map(source.evaluate(x, y), from.0, from.1, to.0, to.1),
+            Self::Warp {
+                ref source,
+                x: ref warp_x,
+                y: ref warp_y,
+                strength,
+            } => {
+                let offset_x = warp_x.evaluate(x, y).mul_add(2.0, -1.0) * strength;
+                let offset_y = warp_y.evaluate(x, y).mul_add(2.0, -1.0) * strength;
+                source.evaluate(x + offset_x, y + offset_y)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NoiseGraphError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl Display for NoiseGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Io(ref error) => write!(f, "could not read the noise graph: {error}"),
+            Self::Parse(ref error) => write!(f, "could not parse the noise graph: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for NoiseGraphError {}
+
+impl From<std::io::Error> for NoiseGraphError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for NoiseGraphError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 11;
+    const FREQUENCY: f32 = 0.005;
+    const POINTS: [(f32, f32); 4] = [(0.0, 0.0), (17.5, -3.25), (-120.0, 64.0), (999.0, 1.0)];
+
+    fn constant(value: f32) -> NoiseNode {
+        NoiseNode::Constant(value)
+    }
+
+    fn simplex(seed: u64) -> NoiseNode {
+        NoiseNode::Simplex(Simplex {
+            seed,
+            ..Simplex::default()
+        })
+    }
+
+    /// The unclamped output of `node` at every point.
+    fn evaluate(node: &NoiseNode) -> Vec<f32> {
+        let graph = node.build(SEED, FREQUENCY);
+        POINTS.iter().map(|&(x, y)| graph.evaluate(x, y)).collect()
+    }
+
+    fn assert_outputs(node: &NoiseNode, expected: f32) {
+        for value in evaluate(node) {
+            assert!(
+                (value - expected).abs() < 1e-6,
+                "{node:?} gave {value}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn ridged_peaks_where_the_source_crosses_the_middle() {
+        for (source, expected) in [(0.5, 1.0), (0.0, 0.0), (1.0, 0.0), (0.75, 0.5)] {
+            assert_outputs(&NoiseNode::Ridged(Box::new(constant(source))), expected);
+        }
+    }
+
+    #[test]
+    fn billow_creases_where_the_source_crosses_the_middle() {
+        for (source, expected) in [(0.5, 0.0), (0.0, 1.0), (1.0, 1.0), (0.25, 0.5)] {
+            assert_outputs(&NoiseNode::Billow(Box::new(constant(source))), expected);
+        }
+    }
+
+    #[test]
+    fn remap_maps_linearly_and_can_invert() {
+        let remap = |source, from, to| NoiseNode::Remap {
+            source: Box::new(constant(source)),
+            from,
+            to,
+        };
+        assert_outputs(&remap(0.25, (0.0, 1.0), (1.0, 0.0)), 0.75);
+        assert_outputs(&remap(0.5, (0.0, 1.0), (0.2, 0.4)), 0.3);
+        // Outside of `from` it keeps going, only the graph's output is clamped.
+        assert_outputs(&remap(2.0, (0.0, 1.0), (0.0, 0.5)), 1.0);
+    }
+
+    #[test]
+    fn clamp_limits_the_source() {
+        let clamp = |source| NoiseNode::Clamp {
+            source: Box::new(constant(source)),
+            min: 0.2,
+            max: 0.8,
+        };
+        assert_outputs(&clamp(1.5), 0.8);
+        assert_outputs(&clamp(-1.0), 0.2);
+        assert_outputs(&clamp(0.5), 0.5);
+    }
+
+    #[test]
+    fn warp_displaces_the_source_by_its_offsets() {
+        let warp = |x, y| NoiseNode::Warp {
+            source: Box::new(simplex(1)),
+            x: Box::new(constant(x)),
+            y: Box::new(constant(y)),
+            strength: 10.0,
+        };
+        let source = simplex(1).build(SEED, FREQUENCY);
+        let sampled = |offset_x: f32, offset_y: f32| -> Vec<f32> {
+            POINTS
+                .iter()
+                .map(|&(x, y)| source.evaluate(x + offset_x, y + offset_y))
+                .collect()
+        };
+        // Offsets of 0.5 are in the middle, so they don't move anything.
+        assert_eq!(evaluate(&warp(0.5, 0.5)), sampled(0.0, 0.0));
+        assert_eq!(evaluate(&warp(1.0, 0.5)), sampled(10.0, 0.0));
+        assert_eq!(evaluate(&warp(0.5, 0.0)), sampled(0.0, -10.0));
+        assert_ne!(evaluate(&warp(1.0, 0.5)), sampled(0.0, 0.0));
+    }
+
+    #[test]
+    fn continents_graph_loads_and_evaluates() {
+        let node = NoiseNode::load("assets/noise/continents.ron")
+            .expect("the bundled noise graph should parse");
+        let graph = node.build(SEED, FREQUENCY);
+        for y in -20..20_i16 {
+            for x in -20..20_i16 {
+                let (x, y) = (f32::from(x) * 37.0, f32::from(y) * 37.0);
+                assert!(graph.evaluate(x, y).is_finite());
+                assert!((0.0..=1.0).contains(&graph.sample(x, y)));
+            }
+        }
+    }
+}
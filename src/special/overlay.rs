@@ -0,0 +1,216 @@
+use macroquad::color::Color;
+use macroquad::color_u8;
+use macroquad::input::{is_key_pressed, KeyCode};
+use macroquad::math::{vec2, Rect};
+use macroquad::shapes::{draw_line, draw_rectangle, draw_rectangle_lines};
+use macroquad::text::draw_text;
+
+use indexmap::IndexMap;
+
+use crate::common::narrow;
+use crate::special::camera::Camera;
+use crate::special::chunk::Chunk;
+use crate::special::config::WorldConfig;
+use crate::world::ChunkPosition;
+
+/// How long a newly generated chunk stays highlighted by the age overlay, in seconds.
+const AGE_FADE: f64 = 10.0;
+
+/// Debug drawing on top of the world, every part toggles on its own:
+/// F1 grid, F2 regions, F3 age, F4 entities, F6 targets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DebugOverlays {
+    /// Chunk borders labelled with their `ChunkPosition`.
+    pub grid: bool,
+    /// Chunks within the render, update and full rate distances.
+    pub regions: bool,
+    /// Seconds since each chunk was generated, new chunks are highlighted.
+    pub age: bool,
+    /// Static and dynamic entities in each chunk.
+    pub entities: bool,
+    /// Lines from followers to what they follow.
+    pub targets: bool,
+}
+
+impl DebugOverlays {
+    pub const NAMES: [&'static str; 5] = ["grid", "regions", "age", "entities", "targets"];
+
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            grid: false,
+            regions: false,
+            age: false,
+            entities: false,
+            targets: false,
+        }
+    }
+
+    pub fn input(&mut self) {
+        let keys = [
+            KeyCode::F1,
+            KeyCode::F2,
+            KeyCode::F3,
+            KeyCode::F4,
+            KeyCode::F6,
+        ];
+        for (key, name) in keys.into_iter().zip(Self::NAMES) {
+            if is_key_pressed(key) {
+                self.toggle(name);
+            }
+        }
+    }
+
+    /// Flips the overlay called `name`, returns whether it is on now or `None` if there
+    /// is no such overlay.
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let flag = match name {
+            "grid" => &mut self.grid,
+            "regions" => &mut self.regions,
+            "age" => &mut self.age,
+            "entities" => &mut self.entities,
+            "targets" => &mut self.targets,
+            _ => return None,
+        };
+        *flag = !*flag;
+        Some(*flag)
+    }
+
+    /// The world space parts, call with the world camera set.
+    pub fn draw_world(
+        &self,
+        chunks: &IndexMap<ChunkPosition, Chunk>,
+        config: &WorldConfig,
+        camera: &Camera,
+        player_chunk: ChunkPosition,
+        time: f64,
+    ) {
+        let view = camera.view_frustum();
+        // Lines stay the same width on screen whatever the zoom.
+        let pixel = camera.viewport_size().0 / camera.screen_size().x;
+        let chunk_size = config.chunk_world_size();
+        let area = |position: ChunkPosition| {
+            let (x, y) = position.offsets(chunk_size);
+            Rect::new(x, y, chunk_size, chunk_size)
+        };
+
+        if self.regions {
+            let distance = config.update_distance.max(config.render_distance);
+            for y in -distance..=distance {
+                for x in -distance..=distance {
+                    let position = player_chunk.add(x, y);
+                    let rect = area(position);
+                    if !view.intersects(rect) {
+                        continue;
+                    }
+                    let color = if position.is_within(player_chunk, config.render_distance) {
+                        color_u8!(60, 220, 90, 40)
+                    } else if position.is_within(player_chunk, config.update_distance) {
+                        color_u8!(240, 170, 40, 35)
+                    } else {
+                        continue;
+                    };
+                    draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+                }
+            }
+            for (distance, color) in [
+                (config.update_distance, color_u8!(240, 170, 40, 200)),
+                (config.render_distance, color_u8!(60, 220, 90, 200)),
+                (config.full_rate_distance, color_u8!(80, 160, 255, 200)),
+            ] {
+                let region = area(player_chunk.add(-distance, -distance))
+                    .combine_with(area(player_chunk.add(distance, distance)));
+                draw_rectangle_lines(region.x, region.y, region.w, region.h, pixel * 4.0, color);
+            }
+        }
+
+        for (&position, chunk) in chunks {
+            let rect = area(position);
+            if !view.intersects(rect) {
+                continue;
+            }
+            if self.age {
+                let fresh = 1.0 - (time - chunk.generated_at) / AGE_FADE;
+                if fresh > 0.0 {
+                    let alpha = narrow(fresh) * 0.4;
+                    draw_rectangle(
+                        rect.x,
+                        rect.y,
+                        rect.w,
+                        rect.h,
+                        Color::new(0.3, 0.6, 1.0, alpha),
+                    );
+                }
+            }
+            if self.grid {
+                let color = color_u8!(255, 255, 255, 120);
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, pixel * 2.0, color);
+            }
+        }
+
+        if self.targets {
+            let color = color_u8!(255, 70, 70, 220);
+            for dynamic in chunks.values().flat_map(|chunk| chunk.dynamics.iter()) {
+                if let Some(target) = dynamic.target() {
+                    let position = dynamic.position;
+                    draw_line(
+                        position.x,
+                        position.y,
+                        target.x,
+                        target.y,
+                        pixel * 2.0,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Per chunk labels, drawn in screen space so they stay readable at any zoom.
+    pub fn draw_labels(
+        &self,
+        chunks: &IndexMap<ChunkPosition, Chunk>,
+        config: &WorldConfig,
+        camera: &Camera,
+        time: f64,
+    ) {
+        if !(self.grid || self.age || self.entities) {
+            return;
+        }
+        let view = camera.view_frustum();
+        let chunk_size = config.chunk_world_size();
+        let color = color_u8!(255, 255, 255, 220);
+        for (&position, chunk) in chunks {
+            let origin = chunk.origin();
+            if !view.intersects(Rect::new(origin.x, origin.y, chunk_size, chunk_size)) {
+                continue;
+            }
+            let mut lines = Vec::new();
+            if self.grid {
+                lines.push(position.to_string());
+            }
+            if self.age {
+                lines.push(format!("age: {:.0}s", time - chunk.generated_at));
+            }
+            if self.entities {
+                lines.push(format!(
+                    "statics: {}, dynamics: {}",
+                    chunk.statics().len(),
+                    chunk.dynamics.len()
+                ));
+            }
+            let mut corner = camera.world_to_screen(origin) + vec2(6.0, 18.0);
+            for text in &lines {
+                draw_text(text, corner.x, corner.y, 20.0, color);
+                corner.y += 18.0;
+            }
+        }
+    }
+}
+
+impl Default for DebugOverlays {
+    fn default() -> Self {
+        Self::new()
+    }
+}
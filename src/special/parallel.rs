@@ -0,0 +1,95 @@
+use std::num::NonZeroUsize;
+use std::thread;
+
+use macroquad::logging::warn;
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Threads to use when the config leaves it at 0, one per core.
+#[must_use]
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Worker threads kept between ticks, so a tick doesn't start threads of its own.
+pub struct WorkerPool {
+    threads: usize,
+    /// `None` with a single thread, or when the threads couldn't be started, then
+    /// everything runs on the calling thread.
+    pool: Option<ThreadPool>,
+}
+
+impl WorkerPool {
+    #[must_use]
+    pub fn new(threads: usize) -> Self {
+        let threads = threads.max(1);
+        let pool = (threads > 1)
+            .then(|| {
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .thread_name(|index| format!("chunk worker {index}"))
+                    .build()
+                    .map_err(|error| warn!("Running chunk updates on one thread: {}", error))
+                    .ok()
+            })
+            .flatten();
+        Self { threads, pool }
+    }
+
+    #[must_use]
+    pub const fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Calls `f` on every item spread over the workers, results keep the order.
+    ///
+    /// Each item is only ever seen by one thread, so as long as `f` only reads shared
+    /// state, the results don't depend on the number of threads.
+    pub fn map_mut<T, R, F>(&self, items: &mut [T], f: F) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(&mut T) -> R + Sync,
+    {
+        match self.pool {
+            Some(ref pool) if items.len() > 1 => {
+                pool.install(|| items.par_iter_mut().map(&f).collect())
+            }
+            _ => items.iter_mut().map(f).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn results_keep_the_order_for_any_number_of_threads() {
+        let expected: Vec<u32> = (0..37).map(|i| i * 2).collect();
+        for threads in [0, 1, 2, 5, 64] {
+            let workers = WorkerPool::new(threads);
+            let mut items: Vec<u32> = (0..37).collect();
+            let doubled = workers.map_mut(&mut items, |item| {
+                *item += 1;
+                (*item - 1) * 2
+            });
+            assert_eq!(doubled, expected);
+            assert_eq!(items, (1..38).collect::<Vec<u32>>());
+        }
+    }
+
+    #[test]
+    fn workers_are_reused_between_calls() {
+        let workers = WorkerPool::new(3);
+        let mut seen = HashSet::new();
+        for _ in 0..10 {
+            let mut items: Vec<u32> = (0..64).collect();
+            seen.extend(workers.map_mut(&mut items, |_| thread::current().id()));
+        }
+        assert!(!seen.contains(&thread::current().id()));
+        assert!(seen.len() <= 3, "ran on {} threads", seen.len());
+    }
+}
@@ -5,6 +5,9 @@ use macroquad::prelude::*;
 
 use macroquad::ui::{hash, root_ui, widgets::Window, Ui};
 
+use crate::common::{floor_i32, narrow};
+use crate::special::zones::Report;
+
 pub struct ProfilerState {
     fps_buffer: Vec<f32>,
     frames_buffer: Vec<telemetry::Frame>,
@@ -25,8 +28,10 @@ impl Default for ProfilerParams {
     }
 }
 
-const FPS_BUFFER_CAPACITY: usize = 100;
-const FRAMES_BUFFER_CAPACITY: usize = 400;
+const FPS_BUFFER_CAPACITY: u16 = 100;
+const FRAMES_BUFFER_CAPACITY: u16 = 400;
+const PROFILE_CSV: &str = "profile.csv";
+const PROFILE_JSON: &str = "profile.json";
 
 fn profiler_window(ui: &mut Ui, state: &mut ProfilerState) {
     fn zone_ui(ui: &mut Ui, zone: &telemetry::Zone, n: usize) {
@@ -61,26 +66,36 @@ fn profiler_window(ui: &mut Ui, state: &mut ProfilerState) {
 
     // select the slowest frame among the ones close to the mouse cursor
     if rect.contains(vec2(mouse_x, mouse_y)) && !state.frames_buffer.is_empty() {
-        let x = ((mouse_x - pos.x - 2.) / w * FRAMES_BUFFER_CAPACITY as f32) as i32;
-
-        let min = clamp(x - 2, 0, state.frames_buffer.len() as i32 - 1) as usize;
-        let max = clamp(x + 3, 0, state.frames_buffer.len() as i32 - 1) as usize;
-
-        selected_frame = state.frames_buffer[min..max]
+        let x =
+            floor_i32((mouse_x - pos.x - 2.) / w * f32::from(FRAMES_BUFFER_CAPACITY)).unwrap_or(0);
+        let last = state.frames_buffer.len() - 1;
+        let index =
+            |offset: i32| usize::try_from(x.saturating_add(offset)).map_or(0, |n| n.min(last));
+
+        let min = index(-2);
+        let max = index(3);
+
+        selected_frame = state
+            .frames_buffer
+            .get(min..max)
+            .unwrap_or_default()
             .iter()
             .enumerate()
-            .max_by(|(_, a), (_, b)| a.full_frame_time.partial_cmp(&b.full_frame_time).unwrap())
+            .max_by(|&(_, a), &(_, b)| a.full_frame_time.total_cmp(&b.full_frame_time))
             .map(|(n, _)| n + min);
     }
 
     if let Some(frame) = selected_frame {
         if is_mouse_button_down(MouseButton::Left) {
-            state.selected_frame = state.frames_buffer[frame].try_clone();
+            state.selected_frame = state
+                .frames_buffer
+                .get(frame)
+                .and_then(telemetry::Frame::try_clone);
         }
     }
-    for (n, frame) in state.frames_buffer.iter().enumerate() {
-        let x = n as f32 / FRAMES_BUFFER_CAPACITY as f32 * (w - 2.);
-        let selected = selected_frame.map_or(false, |selected| n == selected);
+    for (n, frame) in (0_u16..).zip(&state.frames_buffer) {
+        let x = f32::from(n) / f32::from(FRAMES_BUFFER_CAPACITY) * (w - 2.);
+        let selected = selected_frame == Some(usize::from(n));
         let color = if selected {
             Color::new(1.0, 1.0, 0.0, 1.0)
         } else if frame.full_frame_time < 1.0 / 58.0 {
@@ -102,7 +117,7 @@ fn profiler_window(ui: &mut Ui, state: &mut ProfilerState) {
     if let Some(frame) = state
         .selected_frame
         .as_ref()
-        .or_else(|| state.frames_buffer.get(0))
+        .or_else(|| state.frames_buffer.first())
     {
         ui.label(
             None,
@@ -131,7 +146,7 @@ fn profiler_window(ui: &mut Ui, state: &mut ProfilerState) {
     let frame = state
         .selected_frame
         .as_ref()
-        .or_else(|| state.frames_buffer.get(0));
+        .or_else(|| state.frames_buffer.first());
 
     ui.separator();
     ui.group(hash!(), vec2(355., 300.), |ui| {
@@ -145,7 +160,7 @@ fn profiler_window(ui: &mut Ui, state: &mut ProfilerState) {
         let queries = telemetry::gpu_queries();
 
         for query in queries {
-            let t = query.1 as f64 / 1_000_000_000.0;
+            let t = std::time::Duration::from_nanos(query.1).as_secs_f64();
             ui.label(
                 None,
                 &format!("{}: {:.3}ms {:.1}(1/t)", query.0, t, 1.0 / t),
@@ -155,6 +170,15 @@ fn profiler_window(ui: &mut Ui, state: &mut ProfilerState) {
     if ui.button(None, "sample gpu") {
         telemetry::sample_gpu_queries();
     }
+    for (x, path) in [(120.0, PROFILE_CSV), (240.0, PROFILE_JSON)] {
+        ui.same_line(x);
+        if ui.button(None, format!("export {}", path).as_str()) {
+            match Report::from_telemetry(&state.frames_buffer).write(path) {
+                Ok(()) => info!("Exported {} frames to {}", state.frames_buffer.len(), path),
+                Err(error) => warn!("Could not export {}: {}", path, error),
+            }
+        }
+    }
 }
 
 pub fn profiler() {
@@ -181,22 +205,26 @@ pub fn profiler() {
     let time = get_frame_time();
     state.fps_buffer.insert(0, time);
 
-    state.fps_buffer.truncate(FPS_BUFFER_CAPACITY);
-    state.frames_buffer.truncate(FRAMES_BUFFER_CAPACITY);
+    state.fps_buffer.truncate(usize::from(FPS_BUFFER_CAPACITY));
+    state
+        .frames_buffer
+        .truncate(usize::from(FRAMES_BUFFER_CAPACITY));
 
     push_camera_state();
     set_default_camera();
     let mut sum = 0.0;
-    for (x, time) in state.fps_buffer.iter().enumerate() {
+    let mut count = 0.0;
+    for (x, time) in (0_u16..).zip(&state.fps_buffer) {
         draw_line(
-            x as f32 + params.fps_counter_pos.x,
+            f32::from(x) + params.fps_counter_pos.x,
             params.fps_counter_pos.y + 100.0,
-            x as f32 + params.fps_counter_pos.x,
+            f32::from(x) + params.fps_counter_pos.x,
             params.fps_counter_pos.y + 100.0 - (time * 2000.0).min(100.0),
             1.0,
             BLUE,
         );
         sum += time;
+        count += 1.0;
     }
 
     let selectable_rect = Rect::new(
@@ -225,7 +253,7 @@ pub fn profiler() {
     }
 
     draw_text(
-        &format!("{:.1}", 1.0 / (sum / state.fps_buffer.len() as f32)),
+        &format!("{:.1}", 1.0 / (sum / count)),
         params.fps_counter_pos.x,
         params.fps_counter_pos.y + 100.0,
         30.0,
@@ -238,7 +266,7 @@ pub fn profiler() {
             vec2(params.fps_counter_pos.x, params.fps_counter_pos.y + 150.0),
             vec2(525., 450.),
         )
-        .ui(&mut *root_ui(), |ui| {
+        .ui(&mut root_ui(), |ui| {
             let tab = ui.tabbar(
                 hash!(),
                 vec2(300.0, 20.0),
@@ -251,13 +279,18 @@ pub fn profiler() {
                     None,
                     &format!(
                         "scene allocated memory: {:.1} kb",
-                        (telemetry::scene_allocated_memory() as f32) / 1000.0
+                        narrow(
+                            f64::from(
+                                u32::try_from(telemetry::scene_allocated_memory())
+                                    .unwrap_or(u32::MAX)
+                            ) / 1000.0
+                        )
                     ),
                 ),
                 2 => {
                     let drawcalls = telemetry::drawcalls();
                     ui.label(None, &format!("Draw calls: {}", drawcalls.len()));
-                    for telemetry::DrawCallTelemetry { indices_count, .. } in &drawcalls {
+                    for &telemetry::DrawCallTelemetry { indices_count, .. } in &drawcalls {
                         ui.same_line(0.0);
 
                         ui.label(None, &format!("{}", indices_count));
@@ -265,14 +298,14 @@ pub fn profiler() {
                     }
                     ui.label(None, " ");
 
-                    for telemetry::DrawCallTelemetry {
+                    for &telemetry::DrawCallTelemetry {
                         indices_count,
                         texture,
                     } in &drawcalls
                     {
-                        ui.label(None, &format!("{}", *indices_count));
+                        ui.label(None, &format!("{}", indices_count));
                         ui.same_line(0.0);
-                        ui.texture(Texture2D::from_miniquad_texture(*texture), 100., 100.0);
+                        ui.texture(Texture2D::from_miniquad_texture(texture), 100., 100.0);
                         ui.same_line(0.0);
                     }
                     ui.label(None, " ");
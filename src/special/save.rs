@@ -0,0 +1,132 @@
+use std::fmt::Display;
+use std::fs;
+
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::special::config::WorldConfig;
+use crate::special::exploration::Exploration;
+use crate::special::noise_graph::NoiseGraphError;
+
+pub const SAVE_PATH: &str = "venture_save.ron";
+
+/// Everything that can't be regenerated from the seed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub seed: u64,
+    #[serde(default = "default_noise_frequency")]
+    pub noise_frequency: f32,
+    pub player_position: [f32; 2],
+    pub player_rotation: f32,
+    pub exploration: Exploration,
+    /// Saves from before the config existed were made with the defaults.
+    #[serde(default)]
+    pub config: WorldConfig,
+    /// The noise graph file the terrain was built from, `None` for the default graph.
+    #[serde(default)]
+    pub noise_graph: Option<String>,
+}
+
+impl SaveGame {
+    /// # Errors
+    ///
+    /// Fails if the save can't be serialized or written to `path`.
+    pub fn write(&self, path: &str) -> Result<(), SaveError> {
+        let contents = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Fails if `path` can't be read or doesn't contain a valid save.
+    pub fn read(path: &str) -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(ron::from_str(&contents)?)
+    }
+}
+
+fn default_noise_frequency() -> f32 {
+    WorldConfig::default().noise_frequency
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+    NoiseGraph(NoiseGraphError),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Io(ref error) => write!(f, "save file io error: {error}"),
+            Self::Serialize(ref error) => write!(f, "could not serialize the save: {error}"),
+            Self::Parse(ref error) => write!(f, "could not parse the save: {error}"),
+            Self::NoiseGraph(ref error) => {
+                write!(f, "could not load the saved noise graph: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(error: ron::Error) -> Self {
+        Self::Serialize(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<NoiseGraphError> for SaveError {
+    fn from(error: NoiseGraphError) -> Self {
+        Self::NoiseGraph(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::Rect;
+
+    use super::*;
+
+    #[test]
+    fn saves_round_trip_through_the_file() {
+        let config = WorldConfig {
+            chunk_size: 8,
+            render_distance: 3,
+            ..WorldConfig::default()
+        };
+        let mut exploration = Exploration::new();
+        exploration.reveal(Rect::new(-5_000.0, 200.0, 9_000.0, 3_000.0), &config);
+        let save = SaveGame {
+            seed: 0xdead_beef,
+            noise_frequency: 0.007,
+            player_position: [-1_234.5, 678.25],
+            player_rotation: 1.5,
+            exploration,
+            config,
+            noise_graph: Some("assets/noise/continents.ron".to_owned()),
+        };
+
+        let path = std::env::temp_dir().join(format!("venture_save_{}.ron", std::process::id()));
+        let path = path.to_str().expect("temp dir should be utf-8");
+        save.write(path).expect("save should be written");
+        let loaded = SaveGame::read(path);
+        fs::remove_file(path).expect("save should be removed");
+        assert_eq!(loaded.expect("save should load"), save);
+    }
+}
@@ -0,0 +1,323 @@
+use std::f32::consts::TAU;
+use std::fmt::Display;
+
+use macroquad::color::Color;
+use macroquad::color_u8;
+use macroquad::math::{vec2, vec3, Vec2};
+use macroquad::models::{draw_mesh, Mesh, Vertex};
+use macroquad::rand;
+use macroquad::shapes::{draw_circle, draw_line};
+use macroquad::window::{screen_height, screen_width};
+
+use crate::common::{floor_i32, narrow, norm};
+use crate::special::config::WorldConfig;
+use crate::special::noise::Noise;
+
+/// In noise space like the terrain, fronts are much larger than terrain features so they
+/// span several chunks.
+const FRONT_FREQUENCY: f32 = 0.001;
+/// Mixed into the world seed so weather does not mirror the terrain.
+const WEATHER_SEED: u64 = 0x0057_4541_5448_4552;
+/// Temperature is sampled from the same noise layer, far away from the fronts.
+const TEMPERATURE_OFFSET: f32 = 100_000.0;
+/// Front values below this are clear skies.
+const FRONT_THRESHOLD: f32 = 150.0;
+const MAX_PARTICLES: u16 = 600;
+const FOG_SEGMENTS: u16 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Fog,
+    Rain,
+    Snow,
+}
+
+impl Display for WeatherKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Clear => "clear",
+            Self::Fog => "fog",
+            Self::Rain => "rain",
+            Self::Snow => "snow",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WeatherSample {
+    pub kind: WeatherKind,
+    pub intensity: f32,
+}
+
+impl WeatherSample {
+    #[must_use]
+    pub const fn clear() -> Self {
+        Self {
+            kind: WeatherKind::Clear,
+            intensity: 0.0,
+        }
+    }
+
+    /// Multiplier applied to the speed of anything walking through this weather.
+    #[must_use]
+    pub fn speed_multiplier(&self) -> f32 {
+        let slowdown = match self.kind {
+            WeatherKind::Clear => 0.0_f32,
+            WeatherKind::Fog => 0.1,
+            WeatherKind::Rain => 0.3,
+            WeatherKind::Snow => 0.5,
+        };
+        slowdown.mul_add(-self.intensity, 1.0)
+    }
+
+    /// Fraction of the normal view distance that stays visible.
+    #[must_use]
+    pub fn visibility(&self) -> f32 {
+        let obscured = match self.kind {
+            WeatherKind::Clear => 0.0_f32,
+            WeatherKind::Fog => 0.7,
+            WeatherKind::Rain => 0.2,
+            WeatherKind::Snow => 0.4,
+        };
+        obscured.mul_add(-self.intensity, 1.0)
+    }
+}
+
+impl Default for WeatherSample {
+    fn default() -> Self {
+        Self::clear()
+    }
+}
+
+impl Display for WeatherSample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:.0}%", self.kind, self.intensity * 100.0)
+    }
+}
+
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+pub struct Weather {
+    fronts: Noise,
+    /// Sets the noise scale, so fronts keep their size relative to the terrain.
+    config: WorldConfig,
+    wind: Vec2,
+    time: f64,
+
+    local: WeatherSample,
+    particles: Vec<Particle>,
+}
+
+impl Weather {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            fronts: Noise::new(),
+            config: WorldConfig::default(),
+            wind: vec2(150.0, 60.0),
+            time: 0.0,
+            local: WeatherSample::clear(),
+            particles: Vec::new(),
+        }
+    }
+
+    pub fn setup(&mut self, seed: u64, config: WorldConfig) {
+        self.config = config;
+        self.fronts.set_noise(seed ^ WEATHER_SEED, FRONT_FREQUENCY);
+        self.particles.clear();
+    }
+
+    /// Weather at any world position, for the current weather time.
+    #[must_use]
+    pub fn sample(&self, position: Vec2) -> WeatherSample {
+        // The whole noise field drifts with the wind.
+        let drifted = position - self.wind * narrow(self.time);
+        let front = self.fronts.sample_world(drifted, &self.config);
+        if front < FRONT_THRESHOLD {
+            return WeatherSample::clear();
+        }
+        let intensity = norm(front, FRONT_THRESHOLD, 255.0).clamp(0.0, 1.0);
+        let temperature = drifted + vec2(TEMPERATURE_OFFSET, -TEMPERATURE_OFFSET);
+        let temperature = self.fronts.sample_world(temperature, &self.config);
+        let kind = if intensity < 0.3 {
+            WeatherKind::Fog
+        } else if temperature < 110.0 {
+            WeatherKind::Snow
+        } else {
+            WeatherKind::Rain
+        };
+        WeatherSample { kind, intensity }
+    }
+
+    /// Weather at the position passed to the last `update`.
+    #[must_use]
+    pub const fn local(&self) -> WeatherSample {
+        self.local
+    }
+
+    pub fn update(&mut self, time: f64, delta: f32, observer: Vec2) {
+        self.time = time;
+        self.local = self.sample(observer);
+        self.update_particles(delta);
+    }
+
+    fn update_particles(&mut self, delta: f32) {
+        let (width, height) = (screen_width(), screen_height());
+        let wanted = match self.local.kind {
+            WeatherKind::Rain | WeatherKind::Snow => {
+                floor_i32(f32::from(MAX_PARTICLES) * self.local.intensity)
+                    .and_then(|wanted| usize::try_from(wanted).ok())
+                    .unwrap_or(0)
+            }
+            WeatherKind::Clear | WeatherKind::Fog => 0,
+        };
+        self.particles.truncate(wanted);
+        while self.particles.len() < wanted {
+            let velocity = self.particle_velocity();
+            self.particles.push(Particle {
+                position: vec2(rand::gen_range(0.0, width), rand::gen_range(0.0, height)),
+                velocity,
+            });
+        }
+
+        let wind = self.wind.normalize_or_zero();
+        for particle in &mut self.particles {
+            particle.position += particle.velocity * delta;
+            if self.local.kind == WeatherKind::Snow {
+                particle.position.x += rand::gen_range(-1.0, 1.0) + wind.x;
+            }
+            // Wrap around the screen so the density stays constant.
+            particle.position.x = particle.position.x.rem_euclid(width);
+            particle.position.y = particle.position.y.rem_euclid(height);
+        }
+    }
+
+    fn particle_velocity(&self) -> Vec2 {
+        let wind = self.wind.normalize_or_zero();
+        match self.local.kind {
+            WeatherKind::Snow => vec2(wind.x * 20.0, rand::gen_range(40.0, 80.0)),
+            _ => vec2(wind.x * 120.0, rand::gen_range(600.0, 900.0)),
+        }
+    }
+
+    /// How far from the observer anything can be seen, given the size of the full view.
+    #[must_use]
+    pub fn visibility_radius(&self, viewport_size: (f32, f32)) -> f32 {
+        viewport_size.0.max(viewport_size.1) / 2.0 * self.local.visibility()
+    }
+
+    /// Draws the fog around `center` in world space. Past `visibility_radius` the fog
+    /// is opaque.
+    pub fn draw_fog(&self, center: Vec2, viewport_size: (f32, f32)) {
+        if self.local.visibility() >= 1.0 {
+            return;
+        }
+        let radius = self.visibility_radius(viewport_size);
+        let clear = Color::new(0.6, 0.6, 0.65, 0.0);
+        let fog = Color::new(0.6, 0.6, 0.65, 1.0);
+        let inner = radius * 0.5;
+        let outer = viewport_size.0.max(viewport_size.1) * 2.0;
+
+        // Three rings: clear -> fog gradient, then solid fog out past the screen corners.
+        let mut vertices = Vec::with_capacity(usize::from(FOG_SEGMENTS) * 3);
+        for i in 0..FOG_SEGMENTS {
+            let angle = f32::from(i) / f32::from(FOG_SEGMENTS) * TAU;
+            let direction = vec2(angle.cos(), angle.sin());
+            for (distance, color) in [(inner, clear), (radius, fog), (outer, fog)] {
+                let point = center + direction * distance;
+                vertices.push(Vertex {
+                    position: vec3(point.x, point.y, 0.0),
+                    uv: vec2(0.0, 0.0),
+                    color,
+                });
+            }
+        }
+        let mut indices = Vec::with_capacity(usize::from(FOG_SEGMENTS) * 12);
+        for i in 0..FOG_SEGMENTS {
+            let this = i * 3;
+            let next = ((i + 1) % FOG_SEGMENTS) * 3;
+            for ring in 0..2 {
+                indices.extend_from_slice(&[
+                    this + ring,
+                    next + ring,
+                    this + ring + 1,
+                    this + ring + 1,
+                    next + ring,
+                    next + ring + 1,
+                ]);
+            }
+        }
+        draw_mesh(&Mesh {
+            vertices,
+            indices,
+            texture: None,
+        });
+    }
+
+    /// Draws rain and snow in screen space.
+    pub fn draw_particles(&self) {
+        match self.local.kind {
+            WeatherKind::Rain => {
+                let color = color_u8!(150, 170, 255, 120);
+                for particle in &self.particles {
+                    let tail = particle.position - particle.velocity * 0.02;
+                    draw_line(
+                        particle.position.x,
+                        particle.position.y,
+                        tail.x,
+                        tail.y,
+                        1.0,
+                        color,
+                    );
+                }
+            }
+            WeatherKind::Snow => {
+                let color = color_u8!(240, 240, 255, 200);
+                for particle in &self.particles {
+                    draw_circle(particle.position.x, particle.position.y, 2.0, color);
+                }
+            }
+            WeatherKind::Clear | WeatherKind::Fog => (),
+        }
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fronts_scale_with_the_noise_mapping() {
+        let mut weather = Weather::new();
+        weather.setup(4, WorldConfig::default());
+        // Chunks twice as large with the same noise samples spread the noise out twice as far.
+        let mut stretched = Weather::new();
+        stretched.setup(
+            4,
+            WorldConfig {
+                chunk_tile_size: WorldConfig::default().chunk_tile_size * 2.0,
+                ..WorldConfig::default()
+            },
+        );
+        for (x, y) in [(0.0, 0.0), (12_345.0, -6_789.0), (-250_000.0, 40_000.0)] {
+            let position = vec2(x, y);
+            let front = weather.fronts.sample_world(position, &weather.config);
+            let stretched_front = stretched
+                .fronts
+                .sample_world(position * 2.0, &stretched.config);
+            assert!((front - stretched_front).abs() < f32::EPSILON);
+            assert_eq!(weather.sample(position), stretched.sample(position * 2.0));
+        }
+    }
+}
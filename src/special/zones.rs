@@ -0,0 +1,412 @@
+use std::cell::RefCell;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use macroquad::telemetry;
+
+pub const CHUNK_GENERATION: &str = "chunk generation";
+pub const NOISE_CREATION: &str = "noise creation";
+pub const CHUNK_UPDATE: &str = "chunk update";
+pub const ENTITY_HANDOFF: &str = "entity hand-off";
+pub const DRAWING: &str = "drawing";
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Keeps a named zone open until dropped. The zone shows up in the profiler window while
+/// it is open, and in the report of a headless recording.
+#[must_use = "the zone closes when the guard is dropped"]
+pub struct ZoneGuard {
+    _private: (),
+}
+
+pub fn zone(name: &'static str) -> ZoneGuard {
+    telemetry::begin_zone(name);
+    with_recorder(|recorder| recorder.begin(name));
+    ZoneGuard { _private: () }
+}
+
+impl Drop for ZoneGuard {
+    fn drop(&mut self) {
+        with_recorder(Recorder::end);
+        telemetry::end_zone();
+    }
+}
+
+fn with_recorder(f: impl FnOnce(&mut Recorder)) {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            f(recorder);
+        }
+    });
+}
+
+/// Records zones without a window, where macroquad's telemetry can't run. Only one
+/// recording per thread can be active.
+pub fn start_recording() {
+    RECORDER.with(|recorder| *recorder.borrow_mut() = Some(Recorder::new()));
+}
+
+/// Closes the current frame of the recording.
+pub fn finish_frame() {
+    with_recorder(Recorder::finish_frame);
+}
+
+/// Ends the recording, the frame in progress is dropped.
+#[must_use]
+pub fn stop_recording() -> Report {
+    RECORDER
+        .with(|recorder| recorder.borrow_mut().take())
+        .map_or_else(
+            || Report { frames: Vec::new() },
+            |recorder| Report {
+                frames: recorder.frames,
+            },
+        )
+}
+
+struct Recorder {
+    start: Instant,
+    frame_start: f64,
+    frames: Vec<FrameRecord>,
+    /// Finished top level zones of the current frame.
+    zones: Vec<ZoneRecord>,
+    /// Zones that are still open, innermost last.
+    open: Vec<ZoneRecord>,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            frame_start: 0.0,
+            frames: Vec::new(),
+            zones: Vec::new(),
+            open: Vec::new(),
+        }
+    }
+
+    fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    fn begin(&mut self, name: &str) {
+        let start = self.now();
+        self.open.push(ZoneRecord {
+            name: name.to_owned(),
+            start,
+            duration: 0.0,
+            children: Vec::new(),
+        });
+    }
+
+    fn end(&mut self) {
+        let now = self.now();
+        if let Some(mut zone) = self.open.pop() {
+            zone.duration = now - zone.start;
+            match self.open.last_mut() {
+                Some(parent) => parent.children.push(zone),
+                None => self.zones.push(zone),
+            }
+        }
+    }
+
+    fn finish_frame(&mut self) {
+        let now = self.now();
+        self.frames.push(FrameRecord {
+            frame_time: now - self.frame_start,
+            zones: std::mem::take(&mut self.zones),
+        });
+        self.frame_start = now;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZoneRecord {
+    pub name: String,
+    /// Seconds since the game or the recording started.
+    pub start: f64,
+    /// In seconds.
+    pub duration: f64,
+    pub children: Vec<Self>,
+}
+
+impl From<&telemetry::Zone> for ZoneRecord {
+    fn from(zone: &telemetry::Zone) -> Self {
+        Self {
+            name: zone.name.clone(),
+            start: zone.start_time,
+            duration: zone.duration,
+            children: zone.children.iter().map(Self::from).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameRecord {
+    /// In seconds.
+    pub frame_time: f64,
+    pub zones: Vec<ZoneRecord>,
+}
+
+/// The timings of every zone with the same path, over all frames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZoneSummary {
+    pub path: String,
+    pub count: u32,
+    pub total: f64,
+    pub max: f64,
+}
+
+/// Recorded frames, oldest first, from the profiler window or a headless recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub frames: Vec<FrameRecord>,
+}
+
+impl Report {
+    /// `frames` is the profiler's buffer, newest first.
+    #[must_use]
+    pub fn from_telemetry(frames: &[telemetry::Frame]) -> Self {
+        Self {
+            frames: frames
+                .iter()
+                .rev()
+                .map(|frame| FrameRecord {
+                    frame_time: f64::from(frame.full_frame_time),
+                    zones: frame.zones.iter().map(ZoneRecord::from).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes csv, or json if `path` ends in `.json`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be written.
+    pub fn write(&self, path: &str) -> std::io::Result<()> {
+        let json = Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let contents = if json { self.to_json() } else { self.to_csv() };
+        fs::write(path, contents)
+    }
+
+    /// One row per zone, nested zones have their parents' names in `zone`, separated
+    /// by `/`. Times are in milliseconds.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        self.write_csv(&mut csv)
+            .expect("writing to a String can't fail");
+        csv
+    }
+
+    /// The frames with their zone trees. Times are in milliseconds.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json)
+            .expect("writing to a String can't fail");
+        json
+    }
+
+    fn write_csv(&self, csv: &mut impl Write) -> fmt::Result {
+        writeln!(csv, "frame,frame_time_ms,depth,zone,start_ms,duration_ms")?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            for (depth, path, zone) in flatten(&frame.zones) {
+                writeln!(
+                    csv,
+                    "{},{:.4},{},{},{:.4},{:.4}",
+                    index,
+                    frame.frame_time * 1000.0,
+                    depth,
+                    csv_field(&path),
+                    zone.start * 1000.0,
+                    zone.duration * 1000.0
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, json: &mut impl Write) -> fmt::Result {
+        fn write_zones(json: &mut impl Write, zones: &[ZoneRecord]) -> fmt::Result {
+            json.write_char('[')?;
+            for (index, zone) in zones.iter().enumerate() {
+                if index > 0 {
+                    json.write_char(',')?;
+                }
+                json.write_str("{\"name\":")?;
+                write_json_string(json, &zone.name)?;
+                write!(
+                    json,
+                    ",\"start_ms\":{:.4},\"duration_ms\":{:.4},\"children\":",
+                    zone.start * 1000.0,
+                    zone.duration * 1000.0
+                )?;
+                write_zones(json, &zone.children)?;
+                json.write_char('}')?;
+            }
+            json.write_char(']')
+        }
+
+        json.write_str("{\"frames\":[")?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            if index > 0 {
+                json.write_char(',')?;
+            }
+            write!(
+                json,
+                "{{\"frame_time_ms\":{:.4},\"zones\":",
+                frame.frame_time * 1000.0
+            )?;
+            write_zones(json, &frame.zones)?;
+            json.write_char('}')?;
+        }
+        json.write_str("]}\n")
+    }
+
+    /// Every zone path with its timings, in the order they first appear.
+    #[must_use]
+    pub fn summary(&self) -> Vec<ZoneSummary> {
+        let mut summary: Vec<ZoneSummary> = Vec::new();
+        for frame in &self.frames {
+            for (_, path, zone) in flatten(&frame.zones) {
+                match summary.iter_mut().find(|entry| entry.path == path) {
+                    Some(entry) => {
+                        entry.count += 1;
+                        entry.total += zone.duration;
+                        entry.max = entry.max.max(zone.duration);
+                    }
+                    None => summary.push(ZoneSummary {
+                        path,
+                        count: 1,
+                        total: zone.duration,
+                        max: zone.duration,
+                    }),
+                }
+            }
+        }
+        summary
+    }
+}
+
+/// Depth first, with the depth and the full path of every zone.
+fn flatten(zones: &[ZoneRecord]) -> Vec<(usize, String, &ZoneRecord)> {
+    fn visit<'a>(
+        zones: &'a [ZoneRecord],
+        parent: Option<&str>,
+        depth: usize,
+        out: &mut Vec<(usize, String, &'a ZoneRecord)>,
+    ) {
+        for zone in zones {
+            let path = parent.map_or_else(
+                || zone.name.clone(),
+                |parent| format!("{}/{}", parent, zone.name),
+            );
+            out.push((depth, path.clone(), zone));
+            visit(&zone.children, Some(&path), depth + 1, out);
+        }
+    }
+    let mut out = Vec::new();
+    visit(zones, None, 0, &mut out);
+    out
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+fn write_json_string(json: &mut impl Write, text: &str) -> fmt::Result {
+    json.write_char('"')?;
+    for character in text.chars() {
+        match character {
+            '"' => json.write_str("\\\"")?,
+            '\\' => json.write_str("\\\\")?,
+            '\n' => json.write_str("\\n")?,
+            character if character.is_control() => {
+                write!(json, "\\u{:04x}", u32::from(character))?;
+            }
+            character => json.write_char(character)?,
+        }
+    }
+    json.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded() -> Report {
+        start_recording();
+        for _ in 0..2 {
+            {
+                let _outer = zone(CHUNK_UPDATE);
+                let _inner = zone(ENTITY_HANDOFF);
+            }
+            finish_frame();
+        }
+        stop_recording()
+    }
+
+    #[test]
+    fn recording_nests_zones_per_frame() {
+        let report = recorded();
+        assert_eq!(report.frames.len(), 2);
+        for frame in &report.frames {
+            let zones: Vec<(usize, String)> = flatten(&frame.zones)
+                .into_iter()
+                .map(|(depth, path, _)| (depth, path))
+                .collect();
+            assert_eq!(
+                zones,
+                [
+                    (0, CHUNK_UPDATE.to_owned()),
+                    (1, format!("{CHUNK_UPDATE}/{ENTITY_HANDOFF}")),
+                ]
+            );
+        }
+        let summary: Vec<(String, u32)> = report
+            .summary()
+            .into_iter()
+            .map(|entry| (entry.path, entry.count))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("chunk update".to_owned(), 2),
+                ("chunk update/entity hand-off".to_owned(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn zones_outside_a_recording_are_ignored() {
+        drop(zone(DRAWING));
+        assert!(stop_recording().frames.is_empty());
+    }
+
+    #[test]
+    fn csv_and_json_list_every_zone() {
+        let report = recorded();
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 1 + 4);
+        assert!(csv.contains(",1,chunk update/entity hand-off,"));
+        let json = report.to_json();
+        assert!(json.starts_with("{\"frames\":[{\"frame_time_ms\":"));
+        assert_eq!(json.matches("\"name\":\"entity hand-off\"").count(), 2);
+        let mut escaped = String::new();
+        write_json_string(&mut escaped, "a\"b\\").expect("writing to a String can't fail");
+        assert_eq!(escaped, "\"a\\\"b\\\\\"");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+    }
+}
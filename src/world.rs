@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::From;
 use std::default::Default;
 use std::fmt::Display;
@@ -7,66 +8,317 @@ use macroquad::camera::{set_camera, Camera2D};
 use macroquad::color::{colors, Color};
 use macroquad::color_u8;
 use macroquad::input::{
-    is_key_down, is_mouse_button_pressed, mouse_position, KeyCode, MouseButton,
+    is_key_down, is_key_pressed, is_mouse_button_pressed, mouse_position, KeyCode, MouseButton,
 };
-use macroquad::logging::debug;
-use macroquad::math::{vec2, Mat3, Vec2};
+use macroquad::logging::{debug, info, warn};
+use macroquad::math::{vec2, Mat3, Rect, Vec2};
 use macroquad::rand;
-use macroquad::shapes::draw_rectangle_lines;
 use macroquad::telemetry::log_string;
 use macroquad::text::draw_text;
 use macroquad::time::{get_fps, get_time};
 use macroquad::window::{clear_background, screen_height, screen_width};
 
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
-use crate::entity::dynamic::updatable::Update;
-use crate::special::camera::{top_down_camera_controls, Camera};
+use crate::common::{floor_i32, narrow, parse_seed};
+use crate::special::camera::{
+    camera_zoom_controls, top_down_camera_controls, Camera, ResizePolicy,
+};
 use crate::special::chunk::Chunk;
+use crate::special::config::WorldConfig;
+use crate::special::console::{Args, CommandError, CommandRegistry, Console};
+use crate::special::exploration::Exploration;
+use crate::special::inspector::Inspector;
+use crate::special::map::WorldMap;
 use crate::special::noise::Noise;
+use crate::special::noise_graph::NoiseNode;
+use crate::special::overlay::DebugOverlays;
+use crate::special::parallel::WorkerPool;
+use crate::special::save::{SaveError, SaveGame, SAVE_PATH};
 use crate::special::square::Square;
+use crate::special::weather::{Weather, WeatherSample};
+use crate::special::zones;
+
+/// What the camera of a headless world pretends to render to, in pixels.
+const HEADLESS_SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
 
-pub const CHUNK_SIZE: u16 = 16;
-pub const CHUNK_TILE_SIZE: f32 = 400.0;
-pub const NOISE_IMAGE_SIZE: u16 = 256;
-pub const RENDER_DISTANCE: i32 = 2;
-pub const UPDATE_DISTANCE: i32 = 5;
+/// What a world is generated from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldSettings {
+    pub seed: u64,
+    pub noise_frequency: f32,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            noise_frequency: WorldConfig::default().noise_frequency,
+        }
+    }
+}
 
 pub struct World {
     time: Time,
 
-    seed: u64,
+    settings: WorldSettings,
+    noise_graph: NoiseNode,
+    noise_graph_path: Option<String>,
     noise_generators: Vec<Noise>,
+    weather: Weather,
 
     main_camera: Camera,
     player: Square,
+    map: WorldMap,
+    exploration: Exploration,
+    inspector: Inspector,
+    console: Console<Self>,
+    overlays: DebugOverlays,
 
+    config: WorldConfig,
     chunks: IndexMap<ChunkPosition, Chunk>,
-    out_of_chunk: Vec<Option<Box<dyn Update>>>,
+    /// Simulation ticks so far, one per `simulate`.
+    tick: u64,
+    /// Spread chunk updates, rebuilt when `update_threads` changes.
+    workers: WorkerPool,
 }
 
 impl World {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_settings(WorldSettings::default())
+    }
+
+    #[must_use]
+    pub fn with_settings(settings: WorldSettings) -> Self {
+        Self::with_camera(settings, Camera::new())
+    }
+
+    /// A world that can be set up and simulated without a window, it can't be drawn.
+    #[must_use]
+    pub fn headless(settings: WorldSettings) -> Self {
+        Self::with_camera(
+            settings,
+            Camera::with_screen_size(Vec2::from(HEADLESS_SCREEN_SIZE)),
+        )
+    }
+
+    fn with_camera(settings: WorldSettings, main_camera: Camera) -> Self {
         Self {
             time: Time::default(),
-            seed: 0,
+            settings,
+            noise_graph: NoiseNode::default(),
+            noise_graph_path: None,
             noise_generators: Vec::new(),
+            weather: Weather::new(),
 
-            main_camera: Camera::new(),
+            main_camera,
             player: Square::new(vec2(0.0, 0.0)),
+            map: WorldMap::new(),
+            exploration: Exploration::new(),
+            inspector: Inspector::new(),
+            console: Console::new(Self::console_commands()),
+            overlays: DebugOverlays::new(),
 
+            config: WorldConfig::default(),
             chunks: IndexMap::new(),
-            out_of_chunk: Vec::new(),
+            tick: 0,
+            workers: WorkerPool::new(1),
         }
     }
 
     pub fn setup(&mut self) {
         let mut new_noise = Noise::new();
-        new_noise.set_noise(self.seed, 0.005);
+        {
+            let _zone = zones::zone(zones::NOISE_CREATION);
+            new_noise.set_graph(
+                &self.noise_graph,
+                self.settings.seed,
+                self.settings.noise_frequency,
+            );
+        }
         self.noise_generators.push(new_noise);
+        self.weather.setup(self.settings.seed, self.config);
+
+        self.generate_chunks_around(self.chunk_at(self.player.center));
+    }
+
+    /// Takes effect on the next `setup` or `reset`. `path` is the file the graph was loaded
+    /// from, saves keep it so loading them rebuilds the same terrain.
+    pub fn set_noise_graph(&mut self, noise_graph: NoiseNode, path: Option<String>) {
+        self.noise_graph = noise_graph;
+        self.noise_graph_path = path;
+    }
+
+    /// Takes effect on the next `setup` or `reset`, except for the distances.
+    pub const fn set_config(&mut self, config: WorldConfig) {
+        self.config = config;
+    }
+
+    /// The chunk `position` is in.
+    fn chunk_at(&self, position: Vec2) -> ChunkPosition {
+        ChunkPosition::from_world(position, self.config.chunk_world_size())
+    }
+
+    pub const fn set_resize_policy(&mut self, policy: ResizePolicy) {
+        self.main_camera.resize_policy = policy;
+    }
+
+    /// Commands registered here can be run from the in-game console.
+    pub const fn console_commands_mut(&mut self) -> &mut CommandRegistry<Self> {
+        self.console.commands_mut()
+    }
+
+    fn console_commands() -> CommandRegistry<Self> {
+        let mut commands: CommandRegistry<Self> = CommandRegistry::new();
+        commands
+            .register("help", "help", "list the commands", |world, _| {
+                Ok(world.console.commands().help())
+            })
+            .register("clear", "clear", "clear the console", |world, _| {
+                world.console.clear();
+                Ok(String::new())
+            })
+            .register(
+                "seed",
+                "seed <n>",
+                "regenerate the world from a seed",
+                |world, args| {
+                    let seed = parse_seed(args.word(0)?);
+                    world.settings.seed = seed;
+                    world.reset();
+                    Ok(format!("seed: {seed}"))
+                },
+            )
+            .register("tp", "tp <x> <y>", "teleport the player", |world, args| {
+                let position = vec2(args.parse(0)?, args.parse(1)?);
+                let chunk =
+                    ChunkPosition::checked_from_world(position, world.config.chunk_world_size())
+                        .ok_or_else(|| {
+                            CommandError::Failed(format!(
+                                "{}, {} is outside of the world",
+                                position.x, position.y
+                            ))
+                        })?;
+                world.player.center = position;
+                world.main_camera.target = position;
+                world.generate_chunks_around(chunk);
+                Ok(format!("teleported to chunk {chunk}"))
+            })
+            .register(
+                "spawn",
+                "spawn <follower|mover>",
+                "spawn an entity at the player",
+                Self::spawn_command,
+            )
+            .register(
+                "chunk info",
+                "chunk info",
+                "describe the chunk the player is in",
+                Self::chunk_info_command,
+            )
+            .register(
+                "reload chunk",
+                "reload chunk",
+                "regenerate the chunk the player is in",
+                |world, _| {
+                    let position = world.chunk_at(world.player.center);
+                    world.chunks.shift_remove(&position);
+                    world.inspector.clear();
+                    world.generate_chunk(position);
+                    Ok(format!("reloaded chunk {position}"))
+                },
+            )
+            .register(
+                "overlay",
+                "overlay <grid|regions|age|entities|targets>",
+                "toggle a debug overlay, also on F1-F4 and F6",
+                |world, args| {
+                    let name = args.word(0)?;
+                    let on = world
+                        .overlays
+                        .toggle(name)
+                        .ok_or_else(|| args.usage_error())?;
+                    Ok(format!("{}: {}", name, if on { "on" } else { "off" }))
+                },
+            )
+            .register("config", "config", "show the world config", |world, _| {
+                Ok(format!("{:#?}", world.config))
+            })
+            .register(
+                "set",
+                "set <name> <value>",
+                "change a config value, e.g. render_distance",
+                Self::set_command,
+            );
+        commands
+    }
+
+    fn spawn_command(&mut self, args: &Args) -> Result<String, CommandError> {
+        let position = self.player.center;
+        let chunk = self
+            .chunks
+            .get_mut(&self.chunk_at(position))
+            .ok_or_else(|| CommandError::Failed("no chunk here".to_owned()))?;
+        match args.word(0)? {
+            "follower" => chunk.add_follower(position),
+            "mover" => chunk.add_random_mover(position, 0.0, 15.0, 1.0),
+            _ => return Err(args.usage_error()),
+        }
+        Ok(format!("spawned at {:.0}, {:.0}", position.x, position.y))
+    }
+
+    fn chunk_info_command(&mut self, _: &Args) -> Result<String, CommandError> {
+        let position = self.chunk_at(self.player.center);
+        let chunk = self
+            .chunks
+            .get(&position)
+            .ok_or_else(|| CommandError::Failed("no chunk here".to_owned()))?;
+        let origin = chunk.origin();
+        Ok(format!(
+            "chunk: {}\norigin: {:.0}, {:.0}\nstatics: {}, dynamics: {}\nweather: {}",
+            position,
+            origin.x,
+            origin.y,
+            chunk.statics().len(),
+            chunk.dynamics.len(),
+            chunk.weather
+        ))
+    }
 
-        self.generate_chunks_around(ChunkPosition::from(self.player.center));
+    fn set_command(&mut self, args: &Args) -> Result<String, CommandError> {
+        let (name, value) = (args.word(0)?, args.word(1)?);
+        let mut config = self.config;
+        config
+            .set(name, value)
+            .and_then(|()| config.validate())
+            .map_err(|error| CommandError::Failed(error.to_string()))?;
+        self.config = config;
+        // Everything else changes how chunks are laid out, so they have to be regenerated.
+        if !matches!(
+            name,
+            "render_distance"
+                | "update_distance"
+                | "update_threads"
+                | "full_rate_distance"
+                | "far_update_interval"
+        ) {
+            self.reset();
+        }
+        Ok(format!("{name}: {value}"))
+    }
+
+    fn run_command(&mut self, line: &str) {
+        let result = self
+            .console
+            .commands()
+            .find(line)
+            .and_then(|(command, args)| command.run(self, &args));
+        match result {
+            Ok(output) => self.console.print(&output),
+            Err(error) => self.console.print(&error.to_string()),
+        }
     }
 
     fn generate_chunks_around(&mut self, pos: ChunkPosition) {
@@ -83,13 +335,57 @@ impl World {
 
     fn reset(&mut self) {
         self.chunks.clear();
+        self.exploration.clear();
+        self.inspector.clear();
         self.setup();
     }
 
+    /// # Errors
+    ///
+    /// See [`SaveGame::write`].
+    pub fn save(&self, path: &str) -> Result<(), SaveError> {
+        SaveGame {
+            seed: self.settings.seed,
+            noise_frequency: self.settings.noise_frequency,
+            player_position: [self.player.center.x, self.player.center.y],
+            player_rotation: self.player.rotation,
+            exploration: self.exploration.clone(),
+            config: self.config,
+            noise_graph: self.noise_graph_path.clone(),
+        }
+        .write(path)
+    }
+
+    /// # Errors
+    ///
+    /// See [`SaveGame::read`], also fails if the saved noise graph can't be loaded. The world
+    /// is left untouched if the save can't be read.
+    pub fn load(&mut self, path: &str) -> Result<(), SaveError> {
+        let save = SaveGame::read(path)?;
+        let noise_graph = match save.noise_graph {
+            Some(ref graph_path) => NoiseNode::load(graph_path)?,
+            None => NoiseNode::default(),
+        };
+        self.set_noise_graph(noise_graph, save.noise_graph);
+        self.settings = WorldSettings {
+            seed: save.seed,
+            noise_frequency: save.noise_frequency,
+        };
+        self.player.center = Vec2::from(save.player_position);
+        self.player.rotation = save.player_rotation;
+        self.config = save.config;
+        self.reset();
+        self.exploration = save.exploration;
+        Ok(())
+    }
+
     fn generate_chunk(&mut self, pos: ChunkPosition) {
         if !self.chunks.contains_key(&pos) {
-            log_string(format!("Chunk spawn at {}", pos).as_str());
-            let mut chunk = Chunk::new(pos);
+            let _zone = zones::zone(zones::CHUNK_GENERATION);
+            log_string(format!("Chunk spawn at {pos}").as_str());
+            let mut chunk = Chunk::new(pos, self.config);
+            chunk.generated_at = self.time.overall;
+            chunk.simulated_until = self.tick;
             chunk.populate(
                 self.noise_generators
                     .last()
@@ -99,16 +395,52 @@ impl World {
         }
     }
 
+    /// Keyboard shortcuts, ignored while typing into the console.
+    fn hotkeys(&mut self) {
+        if is_key_down(KeyCode::Space) {
+            self.settings.seed = u64::from(rand::rand());
+            self.reset();
+            self.main_camera.shake(0.5);
+        }
+
+        if is_key_pressed(KeyCode::F5) {
+            match self.save(SAVE_PATH) {
+                Ok(()) => info!("Saved to {}", SAVE_PATH),
+                Err(error) => warn!("{}", error),
+            }
+        }
+        if is_key_pressed(KeyCode::F9) {
+            match self.load(SAVE_PATH) {
+                Ok(()) => info!("Loaded {}", SAVE_PATH),
+                Err(error) => warn!("{}", error),
+            }
+        }
+
+        self.map.input(self.player.center);
+        self.overlays.input();
+        if is_key_pressed(KeyCode::H) {
+            self.main_camera.toggle_mode();
+        }
+    }
+
     pub fn input(&mut self) {
+        if let Some(line) = self.console.input() {
+            self.run_command(&line);
+        }
+        // Keys typed into the console don't control the game.
+        let keys = !self.console.is_open();
+
         let lmb = is_mouse_button_pressed(MouseButton::Left);
-        let W = is_key_down(KeyCode::W) || is_key_down(KeyCode::Comma);
-        let S = is_key_down(KeyCode::S) || is_key_down(KeyCode::O);
-        let A = is_key_down(KeyCode::A);
-        let D = is_key_down(KeyCode::D) || is_key_down(KeyCode::E);
+        let forward = keys && (is_key_down(KeyCode::W) || is_key_down(KeyCode::Comma));
+        let back = keys && (is_key_down(KeyCode::S) || is_key_down(KeyCode::O));
+        let turn_left = keys && is_key_down(KeyCode::A);
+        let turn_right = keys && (is_key_down(KeyCode::D) || is_key_down(KeyCode::E));
 
-        if is_key_down(KeyCode::Space) {
-            self.seed = u64::from(rand::rand());
-            self.reset();
+        if keys {
+            self.hotkeys();
+        }
+        if !self.map.open {
+            camera_zoom_controls(&mut self.main_camera);
         }
 
         if lmb {
@@ -127,42 +459,41 @@ impl World {
                 "mouse: {:?}, mouse_world: {}, mouse_chunk: {}",
                 mouse_position(),
                 mouse,
-                ChunkPosition::from(mouse)
+                self.chunk_at(mouse)
             );
+            if !self.map.open && !Inspector::is_mouse_over_ui(Vec2::from(mouse_position())) {
+                self.inspector.pick(&self.chunks, &self.config, mouse);
+            }
         }
+        self.inspector.ui(&mut self.chunks);
 
-        let player_speed = -1.0;
+        let player_speed = -self.weather.local().speed_multiplier();
         self.player.rotation += rand::gen_range(-1., 1.);
         let delta = vec2(0.0, player_speed);
         let rotation_matrix = Mat3::from_rotation_z(self.player.rotation.to_radians());
         self.player.center += rotation_matrix.transform_vector2(delta);
 
-        self.generate_chunks_around(ChunkPosition::from(self.player.center));
+        self.generate_chunks_around(self.chunk_at(self.player.center));
 
-        if is_key_down(KeyCode::LeftControl) {
-            top_down_camera_controls(&mut self.main_camera);
+        if keys && is_key_down(KeyCode::LeftControl) {
+            top_down_camera_controls(&mut self.main_camera, narrow(self.time.delta));
         } else {
             let reversed = -1.0;
             let mut delta = vec2(0.0, 0.0);
-            if W {
+            if forward {
                 delta.y += 1.0 * reversed;
-            } else if S {
+            } else if back {
                 delta.y -= 1.0 * reversed;
             }
             let mut rotation = 0.0;
-            if A {
+            if turn_left {
                 rotation += 0.01 * reversed;
-            } else if D {
+            } else if turn_right {
                 rotation -= 0.01 * reversed;
             }
-            let last_chunk = ChunkPosition::from(self.player.center);
             self.player.rotation += rotation;
             let r = Mat3::from_rotation_z(self.player.rotation);
-            self.player.center += r.transform_vector2(delta * player_speed);
-            let chunk = ChunkPosition::from(self.player.center);
-            if last_chunk != chunk {
-                self.generate_chunks_around(ChunkPosition::from(self.player.center));
-            }
+            self.move_player(r.transform_vector2(delta * player_speed));
 
             self.main_camera
                 .set_follow(Some(self.player.center), Some(self.player.rotation));
@@ -171,28 +502,111 @@ impl World {
 
     pub fn update(&mut self) {
         self.update_time(get_time());
-        self.main_camera.update();
+        self.main_camera
+            .resize(vec2(screen_width(), screen_height()));
+        self.main_camera.update(narrow(self.time.delta));
+        self.exploration
+            .reveal(self.main_camera.viewport_rect(), &self.config);
+        self.weather.update(
+            self.time.overall,
+            narrow(self.time.delta),
+            self.player.center,
+        );
+        self.simulate();
+        self.inspector.refresh(&self.chunks, &self.config);
+    }
 
-        let player_chunk = ChunkPosition::from(self.player.center);
-        for (pos, chunk) in &mut self.chunks {
-            if pos.is_within(player_chunk, UPDATE_DISTANCE) {
-                chunk.update();
-                self.out_of_chunk.extend(chunk.extract_outside_entities());
+    /// Updates the chunks around the player and moves entities that left their chunk
+    /// into the one they are in now. Doesn't need a window.
+    ///
+    /// Runs in two phases, each spread over threads. First every chunk updates itself,
+    /// reading a copy of its neighbours' entity positions from before the update. Then
+    /// the entities that left are extracted from every chunk and handed to their new chunk
+    /// in chunk order, so the result is the same with any number of threads.
+    ///
+    /// Chunks within `full_rate_distance` are updated every tick, the rest of the update
+    /// area only every `far_update_interval` ticks, staggered so they don't all land on
+    /// the same tick. Chunks catch up on the ticks they missed, including those spent out
+    /// of range.
+    pub fn simulate(&mut self) {
+        self.tick += 1;
+        let tick = self.tick;
+        let player_chunk = self.chunk_at(self.player.center);
+        let chunk_size = self.config.chunk_world_size();
+        let update_distance = self.config.update_distance;
+        let full_rate_distance = self.config.full_rate_distance;
+        let interval = self.config.far_update_interval.max(1);
+        let threads = self.config.update_threads();
+        if self.workers.threads() != threads {
+            self.workers = WorkerPool::new(threads);
+        }
+        let workers = &self.workers;
+        let is_due = |pos: &ChunkPosition| {
+            pos.is_within(player_chunk, full_rate_distance)
+                || (pos.is_within(player_chunk, update_distance)
+                    && pos.update_slot(interval) == tick % u64::from(interval))
+        };
+        {
+            let _zone = zones::zone(zones::CHUNK_UPDATE);
+            let snapshot: HashMap<ChunkPosition, Vec<Vec2>> = self
+                .chunks
+                .iter()
+                .filter(|&(pos, _)| pos.is_within(player_chunk, update_distance + 1))
+                .map(|(&pos, chunk)| (pos, chunk.dynamics.positions().to_vec()))
+                .collect();
+            let mut updated: Vec<(ChunkPosition, &mut Chunk)> = self
+                .chunks
+                .iter_mut()
+                .filter(|&(pos, _)| is_due(pos))
+                .map(|(&pos, chunk)| (pos, chunk))
+                .collect();
+            for &mut (pos, ref mut chunk) in &mut updated {
+                chunk.weather = self.weather.sample(pos.center(chunk_size));
             }
+            workers.map_mut(&mut updated, |&mut (pos, ref mut chunk)| {
+                let neighbours: Vec<&[Vec2]> = pos
+                    .neighbours()
+                    .filter_map(|neighbour| snapshot.get(&neighbour))
+                    .map(Vec::as_slice)
+                    .collect();
+                chunk.simulate_until(&neighbours, tick, interval);
+            });
         }
 
-        for entity in self.out_of_chunk.drain(..) {
-            let entity = unsafe {
-                // SAFETY Extracted entities can't be None
-                entity.unwrap_unchecked()
-            };
-            let new_chunk = ChunkPosition::from(entity.get_pos());
-            if let Some(chunk) = self.chunks.get_mut(&new_chunk) {
-                chunk.dynamics.push(Some(entity));
+        let _zone = zones::zone(zones::ENTITY_HANDOFF);
+        let mut updated: Vec<&mut Chunk> = self
+            .chunks
+            .iter_mut()
+            .filter(|&(pos, _)| is_due(pos))
+            .map(|(_, chunk)| chunk)
+            .collect();
+        let leavers = workers.map_mut(&mut updated, |chunk| chunk.extract_outside_entities());
+        for mut leavers in leavers {
+            for entity in leavers.drain() {
+                let new_chunk = ChunkPosition::from_world(entity.position, chunk_size);
+                if let Some(chunk) = self.chunks.get_mut(&new_chunk) {
+                    chunk.dynamics.push(entity);
+                }
             }
         }
     }
 
+    /// Moves the player by `offset`, generating the chunks it walks into.
+    pub fn move_player(&mut self, offset: Vec2) {
+        let last_chunk = self.chunk_at(self.player.center);
+        self.player.center += offset;
+        let chunk = self.chunk_at(self.player.center);
+        if last_chunk != chunk {
+            self.generate_chunks_around(chunk);
+        }
+    }
+
+    /// Weather at any world position right now.
+    #[must_use]
+    pub fn weather_at(&self, position: Vec2) -> WeatherSample {
+        self.weather.sample(position)
+    }
+
     fn update_time(&mut self, time: f64) {
         self.time = Time {
             delta: time - self.time.overall,
@@ -201,43 +615,50 @@ impl World {
     }
 
     pub fn draw(&self) {
+        let _zone = zones::zone(zones::DRAWING);
         clear_background(color_u8!(0, 0, 0, 255));
         // Camera space, render game objects
         let zoom = vec2(self.main_camera.zoom.x, -self.main_camera.zoom.y);
         set_camera(&Camera2D {
-            target: self.main_camera.target,
+            target: self.main_camera.center(),
             rotation: -self.main_camera.rotation.to_degrees(),
             zoom,
             ..Camera2D::default()
         });
 
-        let mut viewport = self.main_camera.viewport_rect();
-        viewport.w += CHUNK_TILE_SIZE * 2.0;
-        viewport.h += CHUNK_TILE_SIZE * 2.0;
-        viewport.move_to(vec2(
-            viewport.x - CHUNK_TILE_SIZE,
-            viewport.y - CHUNK_TILE_SIZE,
-        ));
-        let (width, height) = (screen_width(), screen_height());
-        let (center_x, center_y) = (self.main_camera.target.x, self.main_camera.target.y);
-        let top_left_x = center_x - width;
-        let top_left_y = center_y - height;
-        draw_rectangle_lines(
-            top_left_x,
-            top_left_y,
-            width * 2.0,
-            height * 2.0,
-            50.0,
-            color_u8!(50, 120, 100, 100),
-        );
-
-        let player_chunk = ChunkPosition::from(self.player.center);
+        let mut view = self.main_camera.view_frustum();
+        if self.weather.local().visibility() < 1.0 {
+            // Nothing past the fog needs to be drawn.
+            let visible = self
+                .weather
+                .visibility_radius(self.main_camera.viewport_size());
+            view = view.with_clip(Rect::new(
+                self.player.center.x - visible,
+                self.player.center.y - visible,
+                visible * 2.0,
+                visible * 2.0,
+            ));
+        }
+        let player_chunk = self.chunk_at(self.player.center);
         for (pos, chunk) in &self.chunks {
-            if pos.is_within(player_chunk, RENDER_DISTANCE) {
-                chunk.draw(viewport);
+            if pos.is_within(player_chunk, self.config.render_distance)
+                && view.intersects(chunk.bounds())
+            {
+                chunk.draw(&view);
             }
         }
 
+        self.weather
+            .draw_fog(self.player.center, self.main_camera.viewport_size());
+        // On top of the fog, so they show what is hidden too.
+        self.overlays.draw_world(
+            &self.chunks,
+            &self.config,
+            &self.main_camera,
+            player_chunk,
+            self.time.overall,
+        );
+        self.inspector.draw_highlight(&self.chunks);
         self.player.draw();
         self.draw_ui();
     }
@@ -245,15 +666,37 @@ impl World {
     fn draw_ui(&self) {
         // Screen space, render fixed ui
         set_default_camera();
+        self.weather.draw_particles();
+        self.overlays.draw_labels(
+            &self.chunks,
+            &self.config,
+            &self.main_camera,
+            self.time.overall,
+        );
+
+        // Nameplate above the player, wherever the camera puts it.
+        let half_size = self.player.size / 2.0;
+        let player = self.main_camera.world_rect_to_screen(Rect::new(
+            self.player.center.x - half_size,
+            self.player.center.y - half_size,
+            self.player.size,
+            self.player.size,
+        ));
+        draw_text(
+            "player",
+            player.x,
+            player.y - 5.0,
+            16.0,
+            color_u8!(220, 220, 220, 255),
+        );
+        let mouse = self.main_camera.mouse_world_position();
         draw_text(
             &format!(
-                "fps: {}, mouse: {:?}, chunk: {}",
+                "fps: {}, mouse: ({:.0}, {:.0}), chunk: {}",
                 get_fps(),
-                (
-                    self.main_camera.mouse_world_position().x as i32,
-                    self.main_camera.mouse_world_position().y as i32
-                ),
-                ChunkPosition::from(self.main_camera.mouse_world_position())
+                mouse.x.trunc(),
+                mouse.y.trunc(),
+                self.chunk_at(mouse)
             ),
             10.0,
             20.0,
@@ -263,8 +706,10 @@ impl World {
 
         draw_text(
             &format!(
-                "x:{:3.0} y:{:3.0}",
-                self.player.center.x, self.player.center.y
+                "x:{:3.0} y:{:3.0}, weather: {}",
+                self.player.center.x,
+                self.player.center.y,
+                self.weather.local()
             ),
             10.0,
             40.0,
@@ -275,7 +720,7 @@ impl World {
         let statics: usize = self
             .chunks
             .iter()
-            .map(|(_, chunk)| chunk.statics.len())
+            .map(|(_, chunk)| chunk.statics().len())
             .sum();
         let dynamics: usize = self
             .chunks
@@ -283,15 +728,23 @@ impl World {
             .map(|(_, chunk)| chunk.dynamics.len())
             .sum();
         draw_text(
-            &format!(
-                "static entities: {}, dynamic entities: {}",
-                statics, dynamics
-            ),
+            &format!("static entities: {statics}, dynamic entities: {dynamics}"),
             10.0,
             60.0,
             30.0,
             colors::GRAY,
         );
+
+        self.map
+            .draw_minimap(&self.chunks, &self.config, &self.exploration, &self.player);
+        self.map.draw(
+            &self.chunks,
+            &self.config,
+            &self.exploration,
+            &self.player,
+            self.noise_generators.last(),
+        );
+        self.console.ui();
     }
 }
 
@@ -307,16 +760,51 @@ struct Time {
     overall: f64,
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ChunkPosition {
-    x: i32,
-    y: i32,
+    pub x: i32,
+    pub y: i32,
 }
 
 impl ChunkPosition {
+    #[must_use]
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// The chunk a world position is in, `chunk_size` is in world units.
+    ///
+    /// # Panics
+    ///
+    /// If the position is too far out to have a chunk, see [`Self::checked_from_world`].
+    #[must_use]
+    pub fn from_world(position: Vec2, chunk_size: f32) -> Self {
+        Self::checked_from_world(position, chunk_size)
+            .expect("World position should be within the chunk grid.")
+    }
+
+    /// Like [`Self::from_world`], `None` if the position isn't finite or its chunk
+    /// coordinates don't fit an `i32`.
+    #[must_use]
+    pub fn checked_from_world(position: Vec2, chunk_size: f32) -> Option<Self> {
+        Some(Self {
+            x: floor_i32(position.x / chunk_size)?,
+            y: floor_i32(position.y / chunk_size)?,
+        })
+    }
+
     #[must_use]
     pub fn offsets(&self, chunk_size: f32) -> (f32, f32) {
-        (self.x as f32 * chunk_size, self.y as f32 * chunk_size)
+        (
+            narrow(f64::from(self.x) * f64::from(chunk_size)),
+            narrow(f64::from(self.y) * f64::from(chunk_size)),
+        )
+    }
+
+    #[must_use]
+    pub fn center(&self, chunk_size: f32) -> Vec2 {
+        let (x, y) = self.offsets(chunk_size);
+        vec2(chunk_size.mul_add(0.5, x), chunk_size.mul_add(0.5, y))
     }
 
     #[must_use]
@@ -327,6 +815,25 @@ impl ChunkPosition {
         }
     }
 
+    /// Which of every `interval` ticks a far chunk is updated on, neighbours get different
+    /// slots so the updates are spread out.
+    #[must_use]
+    pub fn update_slot(&self, interval: u16) -> u64 {
+        let slot = self
+            .x
+            .wrapping_add(self.y.wrapping_mul(3))
+            .rem_euclid(i32::from(interval.max(1)));
+        u64::from(slot.cast_unsigned())
+    }
+
+    /// The eight chunks around this one.
+    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+        (-1..=1)
+            .flat_map(|y| (-1..=1).map(move |x| (x, y)))
+            .filter(|&offset| offset != (0, 0))
+            .map(move |(x, y)| self.add(x, y))
+    }
+
     #[must_use]
     pub const fn is_within(&self, other: Self, distance: i32) -> bool {
         if (self.y - other.y).abs() > distance {
@@ -339,20 +846,196 @@ impl ChunkPosition {
     }
 }
 
-impl From<Vec2> for ChunkPosition {
-    fn from(position: Vec2) -> Self {
-        let chunk_size = f32::from(CHUNK_SIZE) * CHUNK_TILE_SIZE;
-        let x = (position.x / chunk_size).floor();
-        let y = (position.y / chunk_size).floor();
-        Self {
-            x: x as i32,
-            y: y as i32,
-        }
-    }
-}
-
 impl Display for ChunkPosition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "x:{}, y:{}", self.x, self.y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Rng;
+
+    const CASES: usize = 1000;
+
+    fn random_position(rng: &mut Rng) -> ChunkPosition {
+        let mut coordinate = || rng.range_u32(0, 2001).cast_signed() - 1000;
+        ChunkPosition::new(coordinate(), coordinate())
+    }
+
+    #[test]
+    fn world_positions_round_trip_through_their_chunk() {
+        let mut rng = Rng::new(7);
+        for chunk_size in [1.0, 400.0, WorldConfig::default().chunk_world_size()] {
+            for _ in 0..CASES {
+                let position = random_position(&mut rng);
+                let (x, y) = position.offsets(chunk_size);
+                assert_eq!(ChunkPosition::from_world(vec2(x, y), chunk_size), position);
+                // Anywhere inside the chunk, the far edges belong to the next one.
+                let inside = vec2(
+                    rng.range_f32(0.0, 0.99) * chunk_size,
+                    rng.range_f32(0.0, 0.99) * chunk_size,
+                );
+                assert_eq!(
+                    ChunkPosition::from_world(vec2(x, y) + inside, chunk_size),
+                    position
+                );
+                assert_eq!(
+                    ChunkPosition::from_world(position.center(chunk_size), chunk_size),
+                    position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn negative_coordinates_round_down() {
+        let chunk_size = 400.0;
+        for (world, chunk) in [
+            (vec2(-0.5, -0.5), ChunkPosition::new(-1, -1)),
+            (vec2(-400.0, 0.0), ChunkPosition::new(-1, 0)),
+            (vec2(-400.5, 399.9), ChunkPosition::new(-2, 0)),
+            (vec2(0.0, -1200.0), ChunkPosition::new(0, -3)),
+        ] {
+            assert_eq!(ChunkPosition::from_world(world, chunk_size), chunk);
+        }
+        assert_eq!(
+            ChunkPosition::new(-2, -3).offsets(chunk_size),
+            (-800.0, -1200.0)
+        );
+    }
+
+    #[test]
+    fn positions_outside_of_the_chunk_grid_are_rejected() {
+        let chunk_size = 400.0;
+        for world in [
+            vec2(f32::NAN, 0.0),
+            vec2(0.0, f32::INFINITY),
+            vec2(-1e30, 0.0),
+            vec2(0.0, 1e30),
+        ] {
+            assert_eq!(ChunkPosition::checked_from_world(world, chunk_size), None);
+        }
+        assert_eq!(
+            ChunkPosition::checked_from_world(vec2(-1e9, 1e9), chunk_size),
+            Some(ChunkPosition::new(-2_500_000, 2_500_000))
+        );
+    }
+
+    #[test]
+    fn is_within_is_the_chessboard_distance() {
+        let mut rng = Rng::new(8);
+        for _ in 0..CASES {
+            let a = random_position(&mut rng);
+            let b = a.add(
+                rng.range_u32(0, 13).cast_signed() - 6,
+                rng.range_u32(0, 13).cast_signed() - 6,
+            );
+            let distance = rng.range_u32(0, 8).cast_signed();
+            let chessboard = (a.x - b.x).abs().max((a.y - b.y).abs());
+            assert_eq!(a.is_within(b, distance), chessboard <= distance);
+            assert_eq!(a.is_within(b, distance), b.is_within(a, distance));
+            assert!(a.is_within(a, 0));
+        }
+    }
+
+    fn simulated(update_threads: u16) -> World {
+        let mut world = World::headless(WorldSettings {
+            seed: 3,
+            ..WorldSettings::default()
+        });
+        world.set_config(WorldConfig {
+            chunk_size: 8,
+            chunk_tile_size: 20.0,
+            noise_image_size: 128,
+            update_distance: 3,
+            update_threads,
+            ..WorldConfig::default()
+        });
+        world.setup();
+        for _ in 0..200 {
+            world.move_player(vec2(1.0, 0.5));
+            world.simulate();
+        }
+        world
+    }
+
+    #[test]
+    fn simulation_does_not_depend_on_the_number_of_threads() {
+        let single = simulated(1);
+        assert!(single
+            .chunks
+            .values()
+            .any(|chunk| !chunk.dynamics.is_empty()));
+        for threads in [2, 7] {
+            let parallel = simulated(threads);
+            assert!(single.chunks.keys().eq(parallel.chunks.keys()));
+            for (chunk, other) in single.chunks.values().zip(parallel.chunks.values()) {
+                assert_eq!(chunk.dynamics, other.dynamics);
+            }
+        }
+    }
+
+    #[test]
+    fn far_chunks_are_updated_less_often() {
+        let mut world = World::headless(WorldSettings::default());
+        world.set_config(WorldConfig {
+            chunk_size: 8,
+            noise_image_size: 128,
+            full_rate_distance: 0,
+            update_distance: 1,
+            far_update_interval: 4,
+            ..WorldConfig::default()
+        });
+        world.setup();
+        let mut lagging = false;
+        for _ in 0..10 {
+            world.simulate();
+            for (&position, chunk) in &world.chunks {
+                let lag = world.tick - chunk.simulated_until;
+                if position == ChunkPosition::new(0, 0) {
+                    assert_eq!(lag, 0);
+                } else {
+                    assert!(lag < 4);
+                    lagging |= lag > 0;
+                }
+            }
+        }
+        assert!(lagging);
+    }
+
+    #[test]
+    fn loading_a_save_rebuilds_its_noise_graph() {
+        let graph_path = "assets/noise/continents.ron";
+        let mut world = World::headless(WorldSettings {
+            seed: 5,
+            ..WorldSettings::default()
+        });
+        world.set_noise_graph(
+            NoiseNode::load(graph_path).expect("the bundled noise graph should parse"),
+            Some(graph_path.to_owned()),
+        );
+        world.setup();
+
+        let path = std::env::temp_dir().join(format!("venture_world_{}.ron", std::process::id()));
+        let path = path.to_str().expect("temp dir should be utf-8");
+        world.save(path).expect("save should be written");
+        let mut loaded = World::headless(WorldSettings::default());
+        let result = loaded.load(path);
+        std::fs::remove_file(path).expect("save should be removed");
+        result.expect("save should load");
+
+        assert_eq!(loaded.noise_graph_path.as_deref(), Some(graph_path));
+        let noise = |world: &World| -> Vec<f32> {
+            let noise = world
+                .noise_generators
+                .last()
+                .expect("noise should be set up");
+            (-10..10_i16)
+                .map(|i| noise.get_point(f32::from(i) * 13.0, f32::from(i) * -7.0))
+                .collect()
+        };
+        assert_eq!(noise(&loaded), noise(&world));
+    }
+}